@@ -0,0 +1,109 @@
+#![warn(rust_2018_idioms)]
+
+// Iterators over the bits of a Natural, from Natural::bits and
+// Natural::windows. Both only ever call bit(), so they work the same on
+// every backend.
+
+use super::natural::Natural;
+
+// Each bit from the least significant up to the top set bit. Going
+// backwards gives them most significant first, which is the order
+// left-to-right exponentiation wants.
+pub struct Bits<'a, T> {
+    n: &'a T,
+    front: usize,
+    back: usize,
+}
+
+impl<'a, T: Natural> Bits<'a, T> {
+    pub(super) fn new(n: &'a T) -> Self {
+        Bits {n, front: 0, back: n.significant_bits()}
+    }
+}
+
+impl<'a, T: Natural> Iterator for Bits<'a, T> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        return Some(self.n.bit(self.front - 1));
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T: Natural> DoubleEndedIterator for Bits<'a, T> {
+    fn next_back(&mut self) -> Option<bool> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        return Some(self.n.bit(self.back));
+    }
+}
+
+impl<'a, T: Natural> ExactSizeIterator for Bits<'a, T> {}
+
+// The number cut into width-bit windows at multiples of width, skipping
+// the windows that are all zero. Items are the index of the window's low
+// bit and its value, for k-ary exponentiation and the like.
+pub struct Windows<'a, T> {
+    n: &'a T,
+    width: u32,
+    front: usize,
+    back: usize,
+}
+
+impl<'a, T: Natural> Windows<'a, T> {
+    pub(super) fn new(n: &'a T, width: u32) -> Self {
+        assert!((1..=64).contains(&width), "window width must be 1 to 64 bits");
+        let w = width as usize;
+        let back = n.significant_bits().div_ceil(w);
+        Windows {n, width, front: 0, back}
+    }
+
+    fn window(&self, i: usize) -> (usize, u64) {
+        let low = i * self.width as usize;
+        let mut v: u64 = 0;
+        for j in 0..self.width {
+            if self.n.bit(low + j as usize) {
+                v |= 1 << j;
+            }
+        }
+        return (low, v);
+    }
+}
+
+impl<'a, T: Natural> Iterator for Windows<'a, T> {
+    type Item = (usize, u64);
+
+    fn next(&mut self) -> Option<(usize, u64)> {
+        while self.front < self.back {
+            let w = self.window(self.front);
+            self.front += 1;
+            if w.1 != 0 {
+                return Some(w);
+            }
+        }
+        return None;
+    }
+}
+
+impl<'a, T: Natural> DoubleEndedIterator for Windows<'a, T> {
+    fn next_back(&mut self) -> Option<(usize, u64)> {
+        while self.front < self.back {
+            self.back -= 1;
+            let w = self.window(self.back);
+            if w.1 != 0 {
+                return Some(w);
+            }
+        }
+        return None;
+    }
+}
@@ -0,0 +1,265 @@
+#![warn(rust_2018_idioms)]
+
+// Arithmetic on naturals that reports going negative instead of
+// panicking. The plain operators still panic with "Underflow", these are
+// for callers who want to recover.
+
+use std::error::Error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(super) enum BigArithErrorKind {
+    Underflow,
+}
+
+use BigArithErrorKind::{*};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BigArithError {
+    pub(super) kind: BigArithErrorKind,
+}
+
+impl BigArithError {
+    fn describe(&self) -> &str {
+        match &self.kind {
+            Underflow => "result can't be negative",
+        }
+    }
+}
+
+impl Error for BigArithError {}
+
+impl Display for BigArithError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.describe())
+    }
+}
+
+// What a backend provides: arithmetic that gives None rather than a
+// negative number. These borrow self so that the assigning forms can
+// leave the value alone on failure.
+pub trait TryAdd<U>: Sized {
+    fn try_add(&self, other: U) -> Option<Self>;
+}
+
+pub trait TrySub<U>: Sized {
+    fn try_sub(&self, other: U) -> Option<Self>;
+}
+
+pub trait CheckedAdd<U = Self>: Sized {
+    fn checked_add(self, other: U) -> Result<Self, BigArithError>;
+}
+
+pub trait CheckedSub<U = Self>: Sized {
+    fn checked_sub(self, other: U) -> Result<Self, BigArithError>;
+}
+
+// The in-place forms, the value is unchanged if they fail.
+pub trait CheckedAddAssign<U = Self> {
+    fn checked_add_assign(&mut self, other: U) -> Result<(), BigArithError>;
+}
+
+pub trait CheckedSubAssign<U = Self> {
+    fn checked_sub_assign(&mut self, other: U) -> Result<(), BigArithError>;
+}
+
+// Like the primitives' overflowing_sub, always gives an answer and says
+// whether the true result was negative. There's nothing for a natural
+// to wrap around to, so the answer is the magnitude of the difference.
+pub trait OverflowingSub<U = Self>: Sized {
+    fn overflowing_sub(self, other: U) -> (Self, bool);
+}
+
+// Turns a backend's None into the error, for the macros below.
+pub(super) fn checked_result<T>(v: Option<T>) -> Result<T, BigArithError> {
+    match v {
+        Some(v) => Ok(v),
+        None => Err(BigArithError {kind: Underflow}),
+    }
+}
+
+macro_rules! checked_op {
+    ($T:ident, $f:ident, $TA:ident, $fa:ident, $Try:ident, $tf:ident, [$($g:tt)*] $Y:ty, $U:ty) => {
+        impl<$($g)*> $T<$U> for WrappedNatural<$Y> where
+            $Y: $Try<$U>,
+        {
+            fn $f(self, other: $U) -> Result<Self, BigArithError> {
+                return checked_result((self.0).$tf(other)).map(Self);
+            }
+        }
+
+        impl<$($g)*> $TA<$U> for WrappedNatural<$Y> where
+            $Y: $Try<$U>,
+        {
+            fn $fa(&mut self, other: $U) -> Result<(), BigArithError> {
+                self.0 = checked_result((self.0).$tf(other))?;
+                return Ok(());
+            }
+        }
+    }
+}
+
+macro_rules! checked_with {
+    ([$($g:tt)*] $Y:ty, $U:ty) => {
+        checked_op!(CheckedAdd, checked_add, CheckedAddAssign, checked_add_assign, TryAdd, try_add, [$($g)*] $Y, $U);
+        checked_op!(CheckedSub, checked_sub, CheckedSubAssign, checked_sub_assign, TrySub, try_sub, [$($g)*] $Y, $U);
+    };
+    ($Y:ty, $U:ty) => {
+        checked_with!([] $Y, $U);
+    };
+}
+
+macro_rules! overflowing_with_unsigned {
+    ([$($g:tt)*] $Y:ty, $U:ty) => {
+        impl<$($g)*> OverflowingSub<$U> for WrappedNatural<$Y> {
+            fn overflowing_sub(self, other: $U) -> (Self, bool) {
+                self.overflowing_sub(Self::from(other))
+            }
+        }
+    }
+}
+
+macro_rules! overflowing_with_signed {
+    ([$($g:tt)*] $Y:ty, $U:ty) => {
+        impl<$($g)*> OverflowingSub<$U> for WrappedNatural<$Y> {
+            fn overflowing_sub(self, other: $U) -> (Self, bool) {
+                let m = Self::from((other as i128).unsigned_abs());
+                match other < 0 {
+                    true => (self + m, false),
+                    false => self.overflowing_sub(m),
+                }
+            }
+        }
+    }
+}
+
+macro_rules! checked_with_self {
+    ([$($g:tt)*] $Y:ty) => {
+        impl<$($g)*> OverflowingSub for WrappedNatural<$Y> {
+            fn overflowing_sub(self, other: Self) -> (Self, bool) {
+                match self < other {
+                    true => (other - self, true),
+                    false => (self - other, false),
+                }
+            }
+        }
+
+        impl<$($g)*> CheckedAdd for WrappedNatural<$Y> {
+            fn checked_add(self, other: Self) -> Result<Self, BigArithError> {
+                self.checked_add(other.0)
+            }
+        }
+
+        impl<$($g)*> CheckedAddAssign for WrappedNatural<$Y> {
+            fn checked_add_assign(&mut self, other: Self) -> Result<(), BigArithError> {
+                self.checked_add_assign(other.0)
+            }
+        }
+
+        impl<$($g)*> CheckedSub for WrappedNatural<$Y> {
+            fn checked_sub(self, other: Self) -> Result<Self, BigArithError> {
+                self.checked_sub(other.0)
+            }
+        }
+
+        impl<$($g)*> CheckedSubAssign for WrappedNatural<$Y> {
+            fn checked_sub_assign(&mut self, other: Self) -> Result<(), BigArithError> {
+                self.checked_sub_assign(other.0)
+            }
+        }
+    };
+    ($Y:ty) => {
+        checked_with_self!([] $Y);
+    };
+}
+
+macro_rules! checked_with_all_primitives {
+    ([$($g:tt)*] $Y:ty) => {
+        checked_with!([$($g)*] $Y, u8);
+        checked_with!([$($g)*] $Y, u16);
+        checked_with!([$($g)*] $Y, u32);
+        checked_with!([$($g)*] $Y, u64);
+        checked_with!([$($g)*] $Y, u128);
+        checked_with!([$($g)*] $Y, usize);
+        checked_with!([$($g)*] $Y, i8);
+        checked_with!([$($g)*] $Y, i16);
+        checked_with!([$($g)*] $Y, i32);
+        checked_with!([$($g)*] $Y, i64);
+        checked_with!([$($g)*] $Y, i128);
+        checked_with!([$($g)*] $Y, isize);
+        overflowing_with_unsigned!([$($g)*] $Y, u8);
+        overflowing_with_unsigned!([$($g)*] $Y, u16);
+        overflowing_with_unsigned!([$($g)*] $Y, u32);
+        overflowing_with_unsigned!([$($g)*] $Y, u64);
+        overflowing_with_unsigned!([$($g)*] $Y, u128);
+        overflowing_with_unsigned!([$($g)*] $Y, usize);
+        overflowing_with_signed!([$($g)*] $Y, i8);
+        overflowing_with_signed!([$($g)*] $Y, i16);
+        overflowing_with_signed!([$($g)*] $Y, i32);
+        overflowing_with_signed!([$($g)*] $Y, i64);
+        overflowing_with_signed!([$($g)*] $Y, i128);
+        overflowing_with_signed!([$($g)*] $Y, isize);
+    };
+    ($Y:ty) => {
+        checked_with_all_primitives!([] $Y);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::natural::{*};
+    use super::super::testing::backend_tests;
+
+    backend_tests!(checked, checked_assign, overflowing);
+
+    fn checked<T>() where
+        T: Natural + std::fmt::Debug,
+        T: CheckedSub<u8> + CheckedSub<i32> + CheckedAdd<i64> + CheckedSub,
+    {
+        let five = || T::from(5u8);
+        assert_eq!(five().checked_sub(3u8).unwrap().to_string(), "2");
+        assert_eq!(five().checked_sub(5u8).unwrap().to_string(), "0");
+        assert_eq!(five().checked_sub(6u8), Err(BigArithError {kind: Underflow}));
+        assert_eq!(five().checked_sub(-6i32).unwrap().to_string(), "11");
+        assert_eq!(five().checked_add(-5i64).unwrap().to_string(), "0");
+        assert!(five().checked_add(-6i64).is_err());
+        assert!(five().checked_sub(T::from(7u8)).is_err());
+    }
+
+    fn checked_assign<T>() where
+        T: Natural + std::fmt::Debug,
+        T: CheckedSubAssign<u64> + CheckedAddAssign<i8>,
+    {
+        let mut x = T::from(5u8);
+        assert!(x.checked_sub_assign(9u64).is_err());
+        assert_eq!(x.to_string(), "5");
+        assert!(x.checked_add_assign(-2i8).is_ok());
+        assert_eq!(x.to_string(), "3");
+    }
+
+    fn overflowing<T>() where
+        T: Natural + std::fmt::Debug,
+        T: OverflowingSub + OverflowingSub<u32> + OverflowingSub<i16>,
+    {
+        let five = || T::from(5u8);
+        let (d, negative) = five().overflowing_sub(T::from(8u8));
+        assert_eq!((d.to_string(), negative), ("3".to_string(), true));
+        let (d, negative) = five().overflowing_sub(5u32);
+        assert_eq!((d.to_string(), negative), ("0".to_string(), false));
+        let (d, negative) = five().overflowing_sub(-4i16);
+        assert_eq!((d.to_string(), negative), ("9".to_string(), false));
+        let (d, negative) = five().overflowing_sub(7i16);
+        assert_eq!((d.to_string(), negative), ("2".to_string(), true));
+    }
+
+    #[test]
+    fn error_message() {
+        assert_eq!(
+            BigArithError {kind: Underflow}.to_string(),
+            "result can't be negative"
+        );
+    }
+}
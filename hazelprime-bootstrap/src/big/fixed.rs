@@ -0,0 +1,833 @@
+#![warn(rust_2018_idioms)]
+
+// Naturals of at most LIMBS 64-bit limbs, kept in an array so nothing
+// touches the heap. Sieving and trial factoring do enormous numbers of
+// operations on 64 to 256 bit values, where an allocation per operation
+// would cost more than the arithmetic. Results that don't fit panic with
+// "Overflow", the way the primitives do in debug builds.
+//
+// The inherent methods mirror NativeNatural's, so native_natural! stamps
+// out the WrappedNatural side, once for every LIMBS.
+
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+
+use super::parse::{*};
+use super::sign::{*};
+use super::natural::{*};
+use super::checked::{*};
+use super::limbs;
+use super::radix;
+use super::limbs::Limb;
+use crate::types::Appendage;
+
+use_arith!();
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FixedNatural<const LIMBS: usize> {
+    limbs: [Limb; LIMBS],
+}
+
+pub type Fixed64 = FixedNatural<1>;
+pub type Fixed128 = FixedNatural<2>;
+pub type Fixed192 = FixedNatural<3>;
+pub type Fixed256 = FixedNatural<4>;
+
+fn overflow() -> ! {
+    panic!("Overflow");
+}
+
+impl<const LIMBS: usize> FixedNatural<LIMBS> {
+    pub const BITS: usize = 64 * LIMBS;
+
+    pub const fn zero() -> Self {
+        FixedNatural { limbs: [0; LIMBS] }
+    }
+
+    // None if src doesn't fit.
+    pub fn from_limbs(src: &[Limb]) -> Option<Self> {
+        let len = limbs::normalized_len(src);
+        if len > LIMBS {
+            return None;
+        }
+        let mut r = Self::zero();
+        r.limbs[..len].copy_from_slice(&src[..len]);
+        return Some(r);
+    }
+
+    // All LIMBS of them, high zeros included.
+    pub fn limbs(&self) -> &[Limb] {
+        &self.limbs
+    }
+
+    // The limbs without the high zeros, the way the limbs functions
+    // like them.
+    fn used(&self) -> &[Limb] {
+        &self.limbs[..limbs::normalized_len(&self.limbs)]
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.iter().all(|&x| x == 0)
+    }
+
+    pub fn is_odd(&self) -> bool {
+        LIMBS > 0 && self.limbs[0] & 1 == 1
+    }
+
+    pub fn bit(&self, i: usize) -> bool {
+        match self.limbs.get(i / 64) {
+            Some(&x) => (x >> (i % 64)) & 1 == 1,
+            None => false,
+        }
+    }
+
+    pub fn set_bit(&mut self, i: usize, value: bool) {
+        if i >= Self::BITS {
+            if value {
+                overflow();
+            }
+            return;
+        }
+        if value {
+            self.limbs[i / 64] |= 1 << (i % 64);
+        } else {
+            self.limbs[i / 64] &= !(1 << (i % 64));
+        }
+    }
+
+    pub fn significant_bits(&self) -> usize {
+        match self.limbs.iter().rposition(|&x| x != 0) {
+            Some(l) => (l + 1) * 64 - self.limbs[l].leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    pub fn trailing_zeros(&self) -> Option<usize> {
+        let l = self.limbs.iter().position(|&x| x != 0)?;
+        return Some(l * 64 + self.limbs[l].trailing_zeros() as usize);
+    }
+
+    // self + other and the carry out of the top limb.
+    pub fn carrying_add(&self, other: &Self) -> (Self, bool) {
+        let mut r = Self::zero();
+        let mut carry = false;
+        for i in 0..LIMBS {
+            let (s, c) = self.limbs[i].add_carry(other.limbs[i], carry);
+            r.limbs[i] = s;
+            carry = c;
+        }
+        return (r, carry);
+    }
+
+    // self - other mod 2^BITS and whether it borrowed.
+    pub fn borrowing_sub(&self, other: &Self) -> (Self, bool) {
+        let mut r = Self::zero();
+        let mut borrow = false;
+        for i in 0..LIMBS {
+            let (s, b) = self.limbs[i].sub_borrow(other.limbs[i], borrow);
+            r.limbs[i] = s;
+            borrow = b;
+        }
+        return (r, borrow);
+    }
+
+    // The low and high halves of the full product.
+    pub fn wide_mul(&self, other: &Self) -> (Self, Self) {
+        let mut lo = Self::zero();
+        let mut hi = Self::zero();
+        for i in 0..LIMBS {
+            if self.limbs[i] == 0 {
+                continue;
+            }
+            let mut carry = 0;
+            for j in 0..LIMBS {
+                let slot = match i + j < LIMBS {
+                    true => &mut lo.limbs[i + j],
+                    false => &mut hi.limbs[i + j - LIMBS],
+                };
+                let (l, h) = self.limbs[i].mul_add2(other.limbs[j], *slot, carry);
+                *slot = l;
+                carry = h;
+            }
+            // Earlier rows stop short of limb i + LIMBS.
+            hi.limbs[i] = carry;
+        }
+        return (lo, hi);
+    }
+
+    // num / d, leaving the quotient in q and returning the remainder.
+    // num has at most 2 * LIMBS limbs and q room for as many. One limb
+    // divisors go a limb at a time, longer ones through algorithm D on
+    // the stack.
+    fn divrem_limbs(num: &[Limb], d: &Self, q: &mut [Limb]) -> Self {
+        let n = limbs::normalized_len(&d.limbs);
+        if n == 0 {
+            panic!("Division by zero");
+        }
+        let len = limbs::normalized_len(num);
+        let mut r = Self::zero();
+        if len < n {
+            r.limbs[..len].copy_from_slice(&num[..len]);
+            return r;
+        }
+        if n == 1 {
+            let mut rem = 0;
+            for i in (0..len).rev() {
+                let (qi, ri) = <Limb as Appendage>::div_wide(rem, num[i], d.limbs[0]);
+                q[i] = qi;
+                rem = ri;
+            }
+            r.limbs[0] = rem;
+            return r;
+        }
+        let s = d.limbs[n - 1].leading_zeros();
+        let mut v = [0 as Limb; LIMBS];
+        limbs::shl_small_into(&mut v[..n], &d.limbs[..n], s);
+        let mut buf = [[0 as Limb; LIMBS]; 3];
+        let u = &mut buf.as_flattened_mut()[..len + 1];
+        u[len] = limbs::shl_small_into(&mut u[..len], &num[..len], s);
+        limbs::divrem_shifted(u, &v[..n], q);
+        for i in 0..n {
+            r.limbs[i] = u[i] >> s;
+            if s > 0 && i + 1 < n {
+                r.limbs[i] |= u[i + 1] << (64 - s);
+            }
+        }
+        return r;
+    }
+
+    pub fn div_rem(&self, other: &Self) -> (Self, Self) {
+        let mut q = [[0 as Limb; LIMBS]; 2];
+        let r = Self::divrem_limbs(&self.limbs, other, q.as_flattened_mut());
+        return (FixedNatural { limbs: q[0] }, r);
+    }
+
+    // (hi * 2^BITS + lo) mod m.
+    fn rem_wide(hi: &Self, lo: &Self, m: &Self) -> Self {
+        let num = [lo.limbs, hi.limbs];
+        let mut q = [[0 as Limb; LIMBS]; 2];
+        return Self::divrem_limbs(num.as_flattened(), m, q.as_flattened_mut());
+    }
+
+    // self * other mod m. For many products by one odd modulus, keep a
+    // Montgomery for it instead.
+    pub fn mul_mod(&self, other: &Self, m: &Self) -> Self {
+        if m.is_odd() {
+            return Montgomery::new(m).mul_mod(self, other);
+        }
+        let (lo, hi) = self.wide_mul(other);
+        return Self::rem_wide(&hi, &lo, m);
+    }
+
+    // self^e mod m. Odd moduli, which is all trial factoring ever sees,
+    // go through Montgomery multiplication.
+    pub fn pow_mod(&self, e: &Self, m: &Self) -> Self {
+        if m.is_odd() {
+            return Montgomery::new(m).pow_mod(self, e);
+        }
+        let base = self.div_rem(m).1;
+        let mut r = Self::from(1u8).div_rem(m).1;
+        for i in (0..e.significant_bits()).rev() {
+            r = r.mul_mod(&r, m);
+            if e.bit(i) {
+                r = r.mul_mod(&base, m);
+            }
+        }
+        return r;
+    }
+
+    // Lower case letters, like format!("{:x}") on the primitives.
+    pub fn to_str_radix(&self, radix: u32) -> String {
+        radix::to_str_radix(self.used(), radix)
+    }
+
+    pub fn from_bytes_le(bytes: &[u8]) -> Self {
+        let mut r = Self::zero();
+        for (i, &b) in bytes.iter().enumerate() {
+            if i / 8 >= LIMBS {
+                if b != 0 {
+                    overflow();
+                }
+                continue;
+            }
+            r.limbs[i / 8] |= (b as Limb) << (8 * (i % 8));
+        }
+        return r;
+    }
+
+    pub fn to_bytes_le(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = self.limbs.iter().flat_map(|x| x.to_le_bytes()).collect();
+        while bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+        return bytes;
+    }
+
+    pub fn from_u64_limbs(src: &[u64]) -> Self {
+        match Self::from_limbs(src) {
+            Some(r) => r,
+            None => overflow(),
+        }
+    }
+
+    pub fn to_u64_limbs(&self) -> Vec<u64> {
+        self.used().to_vec()
+    }
+}
+
+// Montgomery multiplication for an odd modulus m, with R = 2^BITS.
+// Setting one up takes two divisions, so callers doing more than one
+// product by the same modulus should keep it around.
+pub struct Montgomery<const LIMBS: usize> {
+    m: FixedNatural<LIMBS>,
+    // -1/m mod 2^64
+    minv: Limb,
+    // R mod m and R^2 mod m
+    r1: FixedNatural<LIMBS>,
+    r2: FixedNatural<LIMBS>,
+}
+
+impl<const LIMBS: usize> Montgomery<LIMBS> {
+    pub fn new(m: &FixedNatural<LIMBS>) -> Self {
+        assert!(m.is_odd());
+        // Newton's iteration doubles the correct bits, and m is its own
+        // inverse to 3 bits.
+        let mut inv = m.limbs[0];
+        for _ in 0..5 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(m.limbs[0].wrapping_mul(inv)));
+        }
+        let one = FixedNatural::from(1u8);
+        let zero = FixedNatural::zero();
+        let r1 = FixedNatural::rem_wide(&one, &zero, m);
+        let r2 = FixedNatural::rem_wide(&r1, &zero, m);
+        return Montgomery {m: *m, minv: inv.wrapping_neg(), r1, r2};
+    }
+
+    // a * b / R mod m for a, b < m, interleaving the reduction with the
+    // multiplication (CIOS) so it fits in LIMBS + 2 words.
+    fn mul(&self, a: &FixedNatural<LIMBS>, b: &FixedNatural<LIMBS>) -> FixedNatural<LIMBS> {
+        let n = &self.m.limbs;
+        let mut t = [0 as Limb; LIMBS];
+        let mut t_top: Limb = 0;
+        for &bi in b.limbs.iter() {
+            let mut c = 0;
+            for (tj, &aj) in t.iter_mut().zip(a.limbs.iter()) {
+                let (l, h) = aj.mul_add2(bi, *tj, c);
+                *tj = l;
+                c = h;
+            }
+            let (s, c1) = t_top.overflowing_add(c);
+            t_top = s;
+            let u = t[0].wrapping_mul(self.minv);
+            let (_, mut c) = u.mul_add2(n[0], t[0], 0);
+            for j in 1..LIMBS {
+                let (l, h) = u.mul_add2(n[j], t[j], c);
+                t[j - 1] = l;
+                c = h;
+            }
+            let (s, c2) = t_top.overflowing_add(c);
+            t[LIMBS - 1] = s;
+            t_top = c1 as Limb + c2 as Limb;
+        }
+        let r = FixedNatural { limbs: t };
+        if t_top != 0 || r >= self.m {
+            return r.borrowing_sub(&self.m).0;
+        }
+        return r;
+    }
+
+    fn reduce(&self, a: &FixedNatural<LIMBS>) -> FixedNatural<LIMBS> {
+        match *a < self.m {
+            true => *a,
+            false => a.div_rem(&self.m).1,
+        }
+    }
+
+    // a * b mod m.
+    pub fn mul_mod(&self, a: &FixedNatural<LIMBS>, b: &FixedNatural<LIMBS>) -> FixedNatural<LIMBS> {
+        let ab = self.mul(&self.reduce(a), &self.reduce(b));
+        return self.mul(&ab, &self.r2);
+    }
+
+    // base^e mod m.
+    pub fn pow_mod(&self, base: &FixedNatural<LIMBS>, e: &FixedNatural<LIMBS>) -> FixedNatural<LIMBS> {
+        let x = self.mul(&self.reduce(base), &self.r2);
+        let mut r = self.r1;
+        for i in (0..e.significant_bits()).rev() {
+            r = self.mul(&r, &r);
+            if e.bit(i) {
+                r = self.mul(&r, &x);
+            }
+        }
+        return self.mul(&r, &FixedNatural::from(1u8));
+    }
+}
+
+impl<const LIMBS: usize> Default for FixedNatural<LIMBS> {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl<const LIMBS: usize> Display for FixedNatural<LIMBS> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.pad_integral(true, "", &self.to_str_radix(10))
+    }
+}
+
+impl<const LIMBS: usize> Unsigned for FixedNatural<LIMBS> {}
+impl<const LIMBS: usize> NotWrapped for FixedNatural<LIMBS> {}
+
+impl<const LIMBS: usize> FromStrRadix for FixedNatural<LIMBS> {
+    fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseBigError> {
+        if !(2..=36).contains(&radix) {
+            return Err(ParseBigError {kind: LibError(format!("radix {} out of range", radix))});
+        }
+        let (negative, digits) = match src.as_bytes().first() {
+            Some(b'-') => (true, &src[1..]),
+            Some(b'+') => (false, &src[1..]),
+            _ => (false, src),
+        };
+        if digits.is_empty() {
+            return Err(ParseBigError {kind: LibError(String::from("no digits"))});
+        }
+        let mut r = Self::zero();
+        for c in digits.chars() {
+            let d = match c.to_digit(radix) {
+                Some(d) => d,
+                None => return Err(ParseBigError {
+                    kind: LibError(format!("invalid digit {:?} for radix {}", c, radix))
+                }),
+            };
+            let mut carry = d as Limb;
+            for x in r.limbs.iter_mut() {
+                let (l, h) = x.mul_add2(radix as Limb, carry, 0);
+                *x = l;
+                carry = h;
+            }
+            if carry != 0 {
+                return Err(ParseBigError {
+                    kind: LibError(format!("doesn't fit in {} bits", Self::BITS))
+                });
+            }
+        }
+        if negative && !r.is_zero() {
+            return Err(ParseBigError {kind: Underflow});
+        }
+        return Ok(r);
+    }
+}
+
+macro_rules! fixed_from_unsigned {
+    ($U:ty) => {
+        impl<const LIMBS: usize> From<$U> for FixedNatural<LIMBS> {
+            fn from(src: $U) -> Self {
+                let mut r = Self::zero();
+                let mut x = src as u128;
+                for l in r.limbs.iter_mut() {
+                    *l = x as Limb;
+                    x = x.checked_shr(64).unwrap_or(0);
+                }
+                if x != 0 {
+                    overflow();
+                }
+                return r;
+            }
+        }
+    }
+}
+
+fixed_from_unsigned!(u8);
+fixed_from_unsigned!(u16);
+fixed_from_unsigned!(u32);
+fixed_from_unsigned!(u64);
+fixed_from_unsigned!(u128);
+fixed_from_unsigned!(usize);
+
+impl<const LIMBS: usize> PartialOrd for FixedNatural<LIMBS> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const LIMBS: usize> Ord for FixedNatural<LIMBS> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs.iter().rev().cmp(other.limbs.iter().rev())
+    }
+}
+
+impl<const LIMBS: usize> Add for FixedNatural<LIMBS> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        match self.carrying_add(&other) {
+            (r, false) => r,
+            (_, true) => overflow(),
+        }
+    }
+}
+
+impl<const LIMBS: usize> AddAssign for FixedNatural<LIMBS> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const LIMBS: usize> Sub for FixedNatural<LIMBS> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        match self.borrowing_sub(&other) {
+            (r, false) => r,
+            (_, true) => panic!("Underflow"),
+        }
+    }
+}
+
+impl<const LIMBS: usize> SubAssign for FixedNatural<LIMBS> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<const LIMBS: usize> Mul for FixedNatural<LIMBS> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let (lo, hi) = self.wide_mul(&other);
+        if !hi.is_zero() {
+            overflow();
+        }
+        return lo;
+    }
+}
+
+impl<const LIMBS: usize> MulAssign for FixedNatural<LIMBS> {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl<const LIMBS: usize> Div for FixedNatural<LIMBS> {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        self.div_rem(&other).0
+    }
+}
+
+impl<const LIMBS: usize> Rem for FixedNatural<LIMBS> {
+    type Output = Self;
+
+    fn rem(self, other: Self) -> Self {
+        self.div_rem(&other).1
+    }
+}
+
+impl<const LIMBS: usize> BitAnd for FixedNatural<LIMBS> {
+    type Output = Self;
+
+    fn bitand(mut self, other: Self) -> Self {
+        for (x, y) in self.limbs.iter_mut().zip(other.limbs.iter()) {
+            *x &= *y;
+        }
+        return self;
+    }
+}
+
+// Shifting set bits off the top is an overflow like any other.
+impl<const LIMBS: usize> Shl<usize> for FixedNatural<LIMBS> {
+    type Output = Self;
+
+    fn shl(self, bits: usize) -> Self {
+        if self.is_zero() {
+            return self;
+        }
+        if self.significant_bits() + bits > Self::BITS {
+            overflow();
+        }
+        let (l, b) = (bits / 64, (bits % 64) as u32);
+        let mut r = Self::zero();
+        for i in (l..LIMBS).rev() {
+            r.limbs[i] = self.limbs[i - l] << b;
+            if b > 0 && i > l {
+                r.limbs[i] |= self.limbs[i - l - 1] >> (64 - b);
+            }
+        }
+        return r;
+    }
+}
+
+impl<const LIMBS: usize> Shr<usize> for FixedNatural<LIMBS> {
+    type Output = Self;
+
+    fn shr(self, bits: usize) -> Self {
+        let (l, b) = (bits / 64, (bits % 64) as u32);
+        let mut r = Self::zero();
+        for i in 0..LIMBS.saturating_sub(l) {
+            r.limbs[i] = self.limbs[i + l] >> b;
+            if b > 0 && i + l + 1 < LIMBS {
+                r.limbs[i] |= self.limbs[i + l + 1] << (64 - b);
+            }
+        }
+        return r;
+    }
+}
+
+macro_rules! fixed_arithmetic_with {
+    ($U:ty) => {
+        impl<const LIMBS: usize> Add<$U> for FixedNatural<LIMBS> {
+            type Output = Self;
+
+            fn add(self, other: $U) -> Self {
+                self + Self::from(other)
+            }
+        }
+
+        impl<const LIMBS: usize> AddAssign<$U> for FixedNatural<LIMBS> {
+            fn add_assign(&mut self, other: $U) {
+                *self += Self::from(other);
+            }
+        }
+
+        impl<const LIMBS: usize> Sub<$U> for FixedNatural<LIMBS> {
+            type Output = Self;
+
+            fn sub(self, other: $U) -> Self {
+                self - Self::from(other)
+            }
+        }
+
+        impl<const LIMBS: usize> SubAssign<$U> for FixedNatural<LIMBS> {
+            fn sub_assign(&mut self, other: $U) {
+                *self -= Self::from(other);
+            }
+        }
+
+        impl<const LIMBS: usize> Mul<$U> for FixedNatural<LIMBS> {
+            type Output = Self;
+
+            fn mul(self, other: $U) -> Self {
+                self * Self::from(other)
+            }
+        }
+
+        impl<const LIMBS: usize> MulAssign<$U> for FixedNatural<LIMBS> {
+            fn mul_assign(&mut self, other: $U) {
+                *self *= Self::from(other);
+            }
+        }
+
+        impl<const LIMBS: usize> Div<$U> for FixedNatural<LIMBS> {
+            type Output = Self;
+
+            fn div(self, other: $U) -> Self {
+                self / Self::from(other)
+            }
+        }
+
+        impl<const LIMBS: usize> Rem<$U> for FixedNatural<LIMBS> {
+            type Output = Self;
+
+            fn rem(self, other: $U) -> Self {
+                self % Self::from(other)
+            }
+        }
+
+        impl<const LIMBS: usize> BitAnd<$U> for FixedNatural<LIMBS> {
+            type Output = Self;
+
+            fn bitand(self, other: $U) -> Self {
+                self & Self::from(other)
+            }
+        }
+    }
+}
+
+fixed_arithmetic_with!(u8);
+fixed_arithmetic_with!(u16);
+fixed_arithmetic_with!(u32);
+fixed_arithmetic_with!(u64);
+fixed_arithmetic_with!(u128);
+fixed_arithmetic_with!(usize);
+
+// Only going negative gives None, overflow still panics.
+impl<const LIMBS: usize> TryAdd<FixedNatural<LIMBS>> for FixedNatural<LIMBS> {
+    fn try_add(&self, other: FixedNatural<LIMBS>) -> Option<Self> {
+        Some(*self + other)
+    }
+}
+
+impl<const LIMBS: usize> TrySub<FixedNatural<LIMBS>> for FixedNatural<LIMBS> {
+    fn try_sub(&self, other: FixedNatural<LIMBS>) -> Option<Self> {
+        match self.borrowing_sub(&other) {
+            (r, false) => Some(r),
+            (_, true) => None,
+        }
+    }
+}
+
+macro_rules! fixed_try_unsigned {
+    ($U:ty) => {
+        impl<const LIMBS: usize> TryAdd<$U> for FixedNatural<LIMBS> {
+            fn try_add(&self, other: $U) -> Option<Self> {
+                self.try_add(Self::from(other))
+            }
+        }
+
+        impl<const LIMBS: usize> TrySub<$U> for FixedNatural<LIMBS> {
+            fn try_sub(&self, other: $U) -> Option<Self> {
+                self.try_sub(Self::from(other))
+            }
+        }
+    }
+}
+
+macro_rules! fixed_try_signed {
+    ($U:ty) => {
+        impl<const LIMBS: usize> TryAdd<$U> for FixedNatural<LIMBS> {
+            fn try_add(&self, other: $U) -> Option<Self> {
+                let m = Self::from((other as i128).unsigned_abs());
+                match other < 0 {
+                    true => self.try_sub(m),
+                    false => self.try_add(m),
+                }
+            }
+        }
+
+        impl<const LIMBS: usize> TrySub<$U> for FixedNatural<LIMBS> {
+            fn try_sub(&self, other: $U) -> Option<Self> {
+                let m = Self::from((other as i128).unsigned_abs());
+                match other < 0 {
+                    true => self.try_add(m),
+                    false => self.try_sub(m),
+                }
+            }
+        }
+    }
+}
+
+fixed_try_unsigned!(u8);
+fixed_try_unsigned!(u16);
+fixed_try_unsigned!(u32);
+fixed_try_unsigned!(u64);
+fixed_try_unsigned!(u128);
+fixed_try_unsigned!(usize);
+fixed_try_signed!(i8);
+fixed_try_signed!(i16);
+fixed_try_signed!(i32);
+fixed_try_signed!(i64);
+fixed_try_signed!(i128);
+fixed_try_signed!(isize);
+
+native_natural!([const LIMBS: usize] FixedNatural<LIMBS>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::native::NativeNatural;
+    use super::super::testing::next;
+
+    fn random<const LIMBS: usize>(state: &mut u64) -> FixedNatural<LIMBS> {
+        let mut r = FixedNatural::zero();
+        let bits = (next(state) % (64 * LIMBS as u64 + 1)) as usize;
+        for l in r.limbs.iter_mut() {
+            *l = next(state);
+        }
+        return r >> (64 * LIMBS - bits);
+    }
+
+    fn native<const LIMBS: usize>(x: &FixedNatural<LIMBS>) -> NativeNatural {
+        NativeNatural::from_limbs(x.limbs())
+    }
+
+    fn agree<const LIMBS: usize>() {
+        let mut state = 0x9e37_79b9_7f4a_7c15;
+        for _ in 0..2000 {
+            let a: FixedNatural<LIMBS> = random(&mut state);
+            let b: FixedNatural<LIMBS> = random(&mut state);
+            let (na, nb) = (native(&a), native(&b));
+            let (sum, carry) = a.carrying_add(&b);
+            if !carry {
+                assert_eq!(native(&sum), na.clone() + nb.clone());
+            }
+            if a >= b {
+                assert_eq!(native(&(a - b)), na.clone() - nb.clone());
+            }
+            assert_eq!(a.cmp(&b), na.cmp(&nb));
+            let (lo, hi) = a.wide_mul(&b);
+            assert_eq!(native(&lo) + (native(&hi) << FixedNatural::<LIMBS>::BITS), na.clone() * nb.clone());
+            if b.is_zero() {
+                continue;
+            }
+            let (q, r) = a.div_rem(&b);
+            assert_eq!((native(&q), native(&r)), na.div_rem(&nb));
+            let s = (next(&mut state) % 64) as usize;
+            if a.significant_bits() + s <= FixedNatural::<LIMBS>::BITS {
+                assert_eq!(native(&(a << s)), na.clone() << s);
+            }
+            assert_eq!(native(&(a >> s)), na.clone() >> s);
+            assert_eq!(native(&a.mul_mod(&a, &b)), (na.clone() * na.clone()) % nb.clone());
+            if b.is_odd() {
+                // Operands at or above the modulus get reduced first.
+                let c = b.borrowing_sub(&a).0;
+                let m = Montgomery::new(&b);
+                assert_eq!(native(&m.mul_mod(&a, &c)), (na.clone() * native(&c)) % nb.clone());
+            }
+            let e: FixedNatural<LIMBS> = FixedNatural::from(next(&mut state) % 100);
+            let mut p = NativeNatural::from(1u8) % nb.clone();
+            for _ in 0..e.limbs()[0] {
+                p = (p * na.clone()) % nb.clone();
+            }
+            assert_eq!(native(&a.pow_mod(&e, &b)), p);
+        }
+    }
+
+    #[test]
+    fn agrees_with_native() {
+        agree::<1>();
+        agree::<2>();
+        agree::<3>();
+        agree::<4>();
+    }
+
+    #[test]
+    fn as_natural() {
+        type T = WrappedNatural<Fixed128>;
+        let a = T::from_str_radix("340282366920938463463374607431768211455", 10).unwrap();
+        assert_eq!(a.to_string(), "340282366920938463463374607431768211455");
+        assert_eq!(format!("{:x}", a.clone() >> 64), "ffffffffffffffff");
+        assert_eq!(a.significant_bits(), 128);
+        assert!(T::from_str_radix("340282366920938463463374607431768211456", 10).is_err());
+        assert_eq!((a.clone() - 5u8) % 7u8, T::from(5u8));
+        assert_eq!(T::from_bytes_be(&a.to_bytes_be()), a);
+        assert!(T::from(3u8).checked_sub(4u8).is_err());
+        let m = Fixed256::from(1000003u32);
+        assert_eq!(Fixed256::from(2u8).pow_mod(&Fixed256::from(1000002u32), &m), Fixed256::from(1u8));
+    }
+
+    #[test]
+    fn as_natural_any_size() {
+        fn ones<T: Natural>(bits: usize) -> T {
+            (T::from(1u8) << bits) - T::from(1u8)
+        }
+        let a: WrappedNatural<FixedNatural<6>> = ones(380);
+        assert_eq!(a.significant_bits(), 380);
+        assert_eq!(a.to_str_radix(16), "f".repeat(95));
+        assert_eq!(a.to_u64_limbs().len(), 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "Overflow")]
+    fn add_overflow() {
+        let _ = Fixed64::from(u64::MAX) + 1u8;
+    }
+
+    #[test]
+    #[should_panic(expected = "Overflow")]
+    fn shl_overflow() {
+        let _ = Fixed128::from(3u8) << 127;
+    }
+}
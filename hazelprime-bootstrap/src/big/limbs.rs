@@ -0,0 +1,518 @@
+#![warn(rust_2018_idioms)]
+
+// Arithmetic on little-endian limb slices, the guts of NativeNatural.
+//
+// Functions that return a Vec return it normalized (no high zero
+// limbs), functions that take slices don't care about normalization
+// unless they say so. Everything is generic over the limb type so the
+// 32-bit paths can be exercised on 64-bit machines.
+
+use std::cmp::Ordering;
+
+use crate::types::Appendage;
+
+// The limb NativeNatural uses unless told otherwise.
+pub type Limb = u64;
+
+// Below this many limbs in the smaller operand, schoolbook wins.
+pub const KARATSUBA_THRESHOLD: usize = 32;
+
+pub fn normalize<L: Appendage>(v: &mut Vec<L>) {
+    while v.last() == Some(&L::ZERO) {
+        v.pop();
+    }
+}
+
+pub fn normalized_len<L: Appendage>(a: &[L]) -> usize {
+    let mut len = a.len();
+    while len > 0 && a[len - 1] == L::ZERO {
+        len -= 1;
+    }
+    return len;
+}
+
+// The limbs of a primitive, normalized.
+pub fn from_u128<L: Appendage>(mut x: u128) -> Vec<L> {
+    let mut v = Vec::new();
+    while x != 0 {
+        v.push(L::from_u64(x as u64));
+        x >>= L::BITS;
+    }
+    return v;
+}
+
+// Compares two normalized numbers.
+pub fn cmp<L: Appendage>(a: &[L], b: &[L]) -> Ordering {
+    if a.len() != b.len() {
+        return a.len().cmp(&b.len());
+    }
+    for i in (0..a.len()).rev() {
+        if a[i] != b[i] {
+            return a[i].cmp(&b[i]);
+        }
+    }
+    return Ordering::Equal;
+}
+
+// out += b, out.len() >= b.len(). Returns the carry out of the top of out.
+pub fn add_to<L: Appendage>(out: &mut [L], b: &[L]) -> bool {
+    assert!(out.len() >= b.len());
+    let mut carry = false;
+    for i in 0..b.len() {
+        let (s, c) = out[i].add_carry(b[i], carry);
+        out[i] = s;
+        carry = c;
+    }
+    let mut i = b.len();
+    while carry && i < out.len() {
+        let (s, c) = out[i].add_carry(L::ZERO, carry);
+        out[i] = s;
+        carry = c;
+        i += 1;
+    }
+    return carry;
+}
+
+// out -= b, out.len() >= b.len(). Returns the borrow out of the top of out.
+pub fn sub_from<L: Appendage>(out: &mut [L], b: &[L]) -> bool {
+    assert!(out.len() >= b.len());
+    let mut borrow = false;
+    for i in 0..b.len() {
+        let (s, c) = out[i].sub_borrow(b[i], borrow);
+        out[i] = s;
+        borrow = c;
+    }
+    let mut i = b.len();
+    while borrow && i < out.len() {
+        let (s, c) = out[i].sub_borrow(L::ZERO, borrow);
+        out[i] = s;
+        borrow = c;
+        i += 1;
+    }
+    return borrow;
+}
+
+pub fn add<L: Appendage>(a: &[L], b: &[L]) -> Vec<L> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut r = Vec::with_capacity(long.len() + 1);
+    r.extend_from_slice(long);
+    r.push(L::ZERO);
+    add_to(&mut r, short);
+    normalize(&mut r);
+    return r;
+}
+
+pub fn and<L: Appendage>(a: &[L], b: &[L]) -> Vec<L> {
+    let mut r: Vec<L> = a.iter().zip(b.iter()).map(|(&x, &y)| x & y).collect();
+    normalize(&mut r);
+    return r;
+}
+
+// a - b, or None if that would be negative. Both normalized.
+pub fn sub<L: Appendage>(a: &[L], b: &[L]) -> Option<Vec<L>> {
+    if cmp(a, b) == Ordering::Less {
+        return None;
+    }
+    let mut r = a.to_vec();
+    let borrow = sub_from(&mut r, b);
+    debug_assert!(!borrow);
+    normalize(&mut r);
+    return Some(r);
+}
+
+// out += a * b for a single limb b, over a.len() limbs. Returns the carry.
+fn addmul_1<L: Appendage>(out: &mut [L], a: &[L], b: L) -> L {
+    let mut carry = L::ZERO;
+    for i in 0..a.len() {
+        let (lo, hi) = a[i].mul_add2(b, out[i], carry);
+        out[i] = lo;
+        carry = hi;
+    }
+    return carry;
+}
+
+// out = a * b, out zeroed with out.len() == a.len() + b.len().
+fn mul_schoolbook<L: Appendage>(out: &mut [L], a: &[L], b: &[L]) {
+    for j in 0..b.len() {
+        let c = addmul_1(&mut out[j..], a, b[j]);
+        out[j + a.len()] = c;
+    }
+}
+
+// out = a * b, out zeroed with out.len() == a.len() + b.len().
+fn mul_into<L: Appendage>(out: &mut [L], a: &[L], b: &[L]) {
+    let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    if b.is_empty() {
+        return;
+    }
+    if b.len() < KARATSUBA_THRESHOLD {
+        mul_schoolbook(out, a, b);
+    } else if a.len() >= 2 * b.len() {
+        // unbalanced, do it in b-sized chunks of a
+        let mut t = vec![L::ZERO; 2 * b.len()];
+        let mut o = 0;
+        while o < a.len() {
+            let end = usize::min(o + b.len(), a.len());
+            for x in t.iter_mut() {
+                *x = L::ZERO;
+            }
+            let tl = end - o + b.len();
+            mul_into(&mut t[..tl], &a[o..end], b);
+            add_to(&mut out[o..], &t[..tl]);
+            o = end;
+        }
+    } else {
+        karatsuba(out, a, b);
+    }
+}
+
+// Karatsuba for b.len() <= a.len() < 2 * b.len().
+fn karatsuba<L: Appendage>(out: &mut [L], a: &[L], b: &[L]) {
+    let m = a.len() / 2;
+    let (a0, a1) = a.split_at(m);
+    let (b0, b1) = b.split_at(m);
+    {
+        let (z0, z2) = out.split_at_mut(2 * m);
+        mul_into(z0, a0, b0);
+        mul_into(z2, a1, b1);
+    }
+    let sa = add(a0, a1);
+    let sb = add(b0, b1);
+    let mut z1 = vec![L::ZERO; sa.len() + sb.len() + 1];
+    mul_into(&mut z1[..sa.len() + sb.len()], &sa, &sb);
+    let z0_len = normalized_len(&out[..2 * m]);
+    let z2_len = normalized_len(&out[2 * m..]);
+    sub_from(&mut z1, &out[..z0_len]);
+    sub_from(&mut z1, &out[2 * m..2 * m + z2_len]);
+    let z1_len = normalized_len(&z1);
+    let carry = add_to(&mut out[m..], &z1[..z1_len]);
+    debug_assert!(!carry);
+}
+
+pub fn mul<L: Appendage>(a: &[L], b: &[L]) -> Vec<L> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut r = vec![L::ZERO; a.len() + b.len()];
+    mul_into(&mut r, a, b);
+    normalize(&mut r);
+    return r;
+}
+
+// v = v * m + c, in place.
+pub fn mul_small_add<L: Appendage>(v: &mut Vec<L>, m: L, c: L) {
+    let mut carry = c;
+    for x in v.iter_mut() {
+        let (lo, hi) = x.mul_add2(m, carry, L::ZERO);
+        *x = lo;
+        carry = hi;
+    }
+    if carry != L::ZERO {
+        v.push(carry);
+    }
+    normalize(v);
+}
+
+// v = v / d in place, returns v % d.
+pub fn divrem_small<L: Appendage>(v: &mut Vec<L>, d: L) -> L {
+    assert!(d != L::ZERO, "division by zero");
+    let mut rem = L::ZERO;
+    for x in v.iter_mut().rev() {
+        let (q, r) = L::div_wide(rem, *x, d);
+        *x = q;
+        rem = r;
+    }
+    normalize(v);
+    return rem;
+}
+
+pub fn shl<L: Appendage>(a: &[L], bits: usize) -> Vec<L> {
+    if a.is_empty() {
+        return Vec::new();
+    }
+    let limbs = bits / (L::BITS as usize);
+    let b = (bits % (L::BITS as usize)) as u32;
+    let mut r = vec![L::ZERO; limbs + a.len() + 1];
+    if b == 0 {
+        r[limbs..limbs + a.len()].copy_from_slice(a);
+    } else {
+        for i in 0..a.len() {
+            r[limbs + i] = r[limbs + i] | (a[i] << b);
+            r[limbs + i + 1] = a[i] >> (L::BITS - b);
+        }
+    }
+    normalize(&mut r);
+    return r;
+}
+
+pub fn shr<L: Appendage>(a: &[L], bits: usize) -> Vec<L> {
+    let limbs = bits / (L::BITS as usize);
+    if limbs >= a.len() {
+        return Vec::new();
+    }
+    let b = (bits % (L::BITS as usize)) as u32;
+    let src = &a[limbs..];
+    let mut r = vec![L::ZERO; src.len()];
+    if b == 0 {
+        r.copy_from_slice(src);
+    } else {
+        for i in 0..src.len() {
+            r[i] = src[i] >> b;
+            if i + 1 < src.len() {
+                r[i] = r[i] | (src[i + 1] << (L::BITS - b));
+            }
+        }
+    }
+    normalize(&mut r);
+    return r;
+}
+
+// Knuth's algorithm D. Both normalized, b non-zero.
+pub fn divrem<L: Appendage>(a: &[L], b: &[L]) -> (Vec<L>, Vec<L>) {
+    assert!(!b.is_empty(), "division by zero");
+    if cmp(a, b) == Ordering::Less {
+        return (Vec::new(), a.to_vec());
+    }
+    if b.len() == 1 {
+        let mut q = a.to_vec();
+        let r = divrem_small(&mut q, b[0]);
+        let mut r = vec![r];
+        normalize(&mut r);
+        return (q, r);
+    }
+    let s = b[b.len() - 1].leading_zeros();
+    let n = b.len();
+    let m = a.len() - n;
+    let v = shl(b, s as usize);
+    let mut u = shl(a, s as usize);
+    u.resize(a.len() + 1, L::ZERO);
+    let mut q = vec![L::ZERO; m + 1];
+    divrem_shifted(&mut u, &v, &mut q);
+    normalize(&mut q);
+    u.truncate(n);
+    let r = shr(&u, s as usize);
+    return (q, r);
+}
+
+// out = a << b for b < L::BITS, out.len() == a.len(). Returns the bits
+// shifted out of the top.
+pub fn shl_small_into<L: Appendage>(out: &mut [L], a: &[L], b: u32) -> L {
+    assert_eq!(out.len(), a.len());
+    if b == 0 {
+        out.copy_from_slice(a);
+        return L::ZERO;
+    }
+    let mut carry = L::ZERO;
+    for (o, &x) in out.iter_mut().zip(a.iter()) {
+        *o = (x << b) | carry;
+        carry = x >> (L::BITS - b);
+    }
+    return carry;
+}
+
+// The loop of algorithm D, without allocating. v is the divisor shifted
+// so its top bit is set, two limbs or more, and u the dividend shifted
+// the same way with a limb to spare on top. Leaves the quotient in
+// q[..u.len() - v.len()] and the shifted remainder in u[..v.len()].
+pub fn divrem_shifted<L: Appendage>(u: &mut [L], v: &[L], q: &mut [L]) {
+    let n = v.len();
+    assert!(n >= 2 && u.len() > n && q.len() >= u.len() - n);
+    let m = u.len() - n - 1;
+    let v_top = v[n - 1];
+    let v_next = v[n - 2];
+    for j in (0..=m).rev() {
+        // qhat = (u[j+n], u[j+n-1]) / v_top, capped at the largest limb.
+        // rhat_big means rhat no longer fits in a limb.
+        debug_assert!(u[j + n] <= v_top);
+        let (mut qhat, mut rhat, mut rhat_big) = if u[j + n] == v_top {
+            let (r, c) = u[j + n - 1].add_carry(v_top, false);
+            (L::MAX, r, c)
+        } else {
+            let (q, r) = L::div_wide(u[j + n], u[j + n - 1], v_top);
+            (q, r, false)
+        };
+        while !rhat_big {
+            let (lo, hi) = qhat.widening_mul(v_next);
+            if (hi, lo) <= (rhat, u[j + n - 2]) {
+                break;
+            }
+            qhat = qhat.sub_borrow(L::ONE, false).0;
+            let (r, c) = rhat.add_carry(v_top, false);
+            rhat = r;
+            rhat_big = c;
+        }
+        // u[j..j+n+1] -= qhat * v
+        let mut carry = L::ZERO;
+        let mut borrow = false;
+        for i in 0..n {
+            let (lo, hi) = qhat.mul_add2(v[i], carry, L::ZERO);
+            carry = hi;
+            let (t, c) = u[i + j].sub_borrow(lo, borrow);
+            u[i + j] = t;
+            borrow = c;
+        }
+        let (t, negative) = u[j + n].sub_borrow(carry, borrow);
+        u[j + n] = t;
+        if negative {
+            // qhat was one too big, add v back
+            qhat = qhat.sub_borrow(L::ONE, false).0;
+            let c = add_to(&mut u[j..j + n], v);
+            u[j + n] = u[j + n].add_carry(L::ZERO, c).0;
+        }
+        q[j] = qhat;
+    }
+}
+
+// B^k, where B is the limb base.
+fn base_pow<L: Appendage>(k: usize) -> Vec<L> {
+    let mut v = vec![L::ZERO; k + 1];
+    v[k] = L::ONE;
+    return v;
+}
+
+// Below this many limbs a reciprocal is just a long division.
+pub const RECIPROCAL_THRESHOLD: usize = 16;
+
+// floor(B^(2s) / d) where d has s limbs. Newton's iteration: the
+// reciprocal of the top half of d, one step to double its precision,
+// then an exact fix-up that only has to divide out a few limbs of error.
+pub fn reciprocal<L: Appendage>(d: &[L]) -> Vec<L> {
+    let s = d.len();
+    assert!(s > 0 && d[s - 1] != L::ZERO);
+    let b2s = base_pow(2 * s);
+    if s <= RECIPROCAL_THRESHOLD {
+        return divrem(&b2s, d).0;
+    }
+    let l = s / 2;
+    let bits = 2 * s * L::BITS as usize;
+    let x0 = shl(&reciprocal(&d[l..]), l * L::BITS as usize);
+    // x1 = x0 + x0 * (B^(2s) - d * x0) / B^(2s)
+    let dx0 = mul(d, &x0);
+    let x1 = match sub(&b2s, &dx0) {
+        Some(e) => add(&x0, &shr(&mul(&x0, &e), bits)),
+        None => {
+            let e = sub(&dx0, &b2s).unwrap();
+            sub(&x0, &shr(&mul(&x0, &e), bits)).unwrap_or_default()
+        },
+    };
+    let dx1 = mul(d, &x1);
+    match sub(&b2s, &dx1) {
+        Some(r) => {
+            return add(&x1, &divrem(&r, d).0);
+        },
+        None => {
+            // x1 is too big by ceil((d * x1 - B^(2s)) / d)
+            let r = sub(&dx1, &b2s).unwrap();
+            let (mut q, rem) = divrem(&r, d);
+            if !rem.is_empty() {
+                q = add(&q, &[L::ONE]);
+            }
+            return sub(&x1, &q).unwrap();
+        },
+    }
+}
+
+// Barrett division: a / d and a % d for a < B^(2s), given
+// recip = reciprocal(d). The estimate is at most two short.
+pub fn divrem_reciprocal<L: Appendage>(a: &[L], d: &[L], recip: &[L]) -> (Vec<L>, Vec<L>) {
+    let s = d.len();
+    assert!(a.len() <= 2 * s);
+    let mut q = shr(&mul(a, recip), 2 * s * L::BITS as usize);
+    let mut r = sub(a, &mul(&q, d)).expect("reciprocal too big");
+    while cmp(&r, d) != Ordering::Less {
+        r = sub(&r, d).unwrap();
+        q = add(&q, &[L::ONE]);
+    }
+    return (q, r);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::testing::next;
+
+    fn random<L: Appendage>(seed: &mut u64, len: usize) -> Vec<L> {
+        let mut v: Vec<L> = (0..len).map(|_| L::from_u64(next(seed))).collect();
+        normalize(&mut v);
+        return v;
+    }
+
+    fn check_karatsuba<L: Appendage>() {
+        let mut seed = 1;
+        for &(la, lb) in &[(32, 32), (33, 40), (64, 64), (100, 37), (150, 149), (300, 32)] {
+            let a: Vec<L> = random(&mut seed, la);
+            let b: Vec<L> = random(&mut seed, lb);
+            let mut s = vec![L::ZERO; a.len() + b.len()];
+            mul_schoolbook(&mut s, &a, &b);
+            normalize(&mut s);
+            assert_eq!(mul(&a, &b), s);
+            assert_eq!(mul(&b, &a), s);
+        }
+    }
+
+    fn check_divrem<L: Appendage>() {
+        let mut seed = 2;
+        for &(la, lb) in &[(1, 1), (5, 2), (40, 7), (80, 40), (3, 3)] {
+            let a: Vec<L> = random(&mut seed, la);
+            let b: Vec<L> = random(&mut seed, lb);
+            let (q, r) = divrem(&a, &b);
+            assert_eq!(cmp(&r, &b), Ordering::Less);
+            let back = add(&mul(&q, &b), &r);
+            assert_eq!(back, a);
+        }
+        // the qhat correction case: all ones over a top-heavy divisor
+        let a = vec![L::MAX; 6];
+        let b = vec![L::ONE, L::ZERO, L::MAX];
+        let (q, r) = divrem(&a, &b);
+        assert_eq!(add(&mul(&q, &b), &r), a);
+    }
+
+    #[test]
+    fn karatsuba_matches_schoolbook() {
+        check_karatsuba::<u64>();
+        check_karatsuba::<u32>();
+    }
+
+    #[test]
+    fn divrem_inverts_mul() {
+        check_divrem::<u64>();
+        check_divrem::<u32>();
+    }
+
+    fn check_reciprocal<L: Appendage>() {
+        let mut seed = 3;
+        for &(ld, la) in &[(1, 2), (16, 30), (17, 34), (40, 61), (133, 266)] {
+            let d: Vec<L> = random(&mut seed, ld);
+            let a: Vec<L> = random(&mut seed, la);
+            let r = reciprocal(&d);
+            assert_eq!(r, divrem(&base_pow(2 * d.len()), &d).0);
+            assert_eq!(divrem_reciprocal(&a, &d, &r), divrem(&a, &d));
+        }
+        // a power of two, where the first estimate is exact
+        let p: Vec<L> = base_pow(50);
+        assert_eq!(reciprocal(&p), base_pow(52));
+    }
+
+    #[test]
+    fn reciprocals() {
+        check_reciprocal::<u64>();
+        check_reciprocal::<u32>();
+    }
+
+    #[test]
+    fn shifts() {
+        let a: Vec<u64> = vec![0x8000_0000_0000_0001, 0x3];
+        assert_eq!(shl(&a, 1), vec![2, 7]);
+        assert_eq!(shr(&shl(&a, 130), 130), a);
+        assert_eq!(shr(&a, 200), Vec::<u64>::new());
+        let b: Vec<u32> = vec![0x8000_0001, 0x3];
+        assert_eq!(shl(&b, 1), vec![2, 7]);
+        assert_eq!(shr(&shl(&b, 70), 70), b);
+    }
+
+    #[test]
+    fn primitives() {
+        assert_eq!(from_u128::<u32>(1 << 70 | 5), vec![5, 0, 64]);
+        assert_eq!(from_u128::<u64>(0), Vec::<u64>::new());
+    }
+}
@@ -1,9 +1,29 @@
 mod parse;
 mod sign;
 #[macro_use] mod natural;
+mod bits;
+#[macro_use] mod checked;
+mod limbs;
+mod radix;
+#[macro_use] mod native;
+mod fixed;
+
+#[cfg(test)]
+pub(crate) mod testing;
 
 #[cfg(feature="rug_integer")]
 mod rug_integer;
 
+pub use parse::{FromStrRadix, ParseBigError};
+pub use natural::{Natural, WrappedNatural};
+pub use bits::{Bits, Windows};
+pub use checked::{BigArithError, CheckedAdd, CheckedSub, CheckedAddAssign, CheckedSubAssign, OverflowingSub};
+pub use native::{NativeNatural, NativeNatural32, NativeNatural64};
+pub use fixed::{FixedNatural, Montgomery, Fixed64, Fixed128, Fixed192, Fixed256};
 
-
+// The backends, ready to use as a Natural.
+pub type Native = WrappedNatural<NativeNatural>;
+pub type Native32 = WrappedNatural<NativeNatural32>;
+pub type Fixed<const LIMBS: usize> = WrappedNatural<FixedNatural<LIMBS>>;
+#[cfg(feature="rug_integer")]
+pub type Rug = WrappedNatural<rug::Integer>;
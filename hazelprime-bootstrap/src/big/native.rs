@@ -0,0 +1,620 @@
+#![warn(rust_2018_idioms)]
+
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+
+use super::parse::{*};
+use super::sign::{*};
+use super::natural::{*};
+use super::checked::{*};
+use super::limbs;
+use super::radix;
+use super::limbs::Limb;
+use crate::types::Appendage;
+
+use_arith!();
+
+// A natural number as a vector of limbs, least significant first, with
+// no high zero limbs. Zero is the empty vector.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct NativeNatural<L: Appendage = Limb> {
+    limbs: Vec<L>,
+}
+
+pub type NativeNatural32 = NativeNatural<u32>;
+pub type NativeNatural64 = NativeNatural<u64>;
+
+impl<L: Appendage> NativeNatural<L> {
+    pub fn zero() -> Self {
+        NativeNatural { limbs: Vec::new() }
+    }
+
+    pub fn from_limbs(src: &[L]) -> Self {
+        let mut limbs = src.to_vec();
+        limbs::normalize(&mut limbs);
+        return NativeNatural { limbs };
+    }
+
+    pub fn limbs(&self) -> &[L] {
+        &self.limbs
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn bit(&self, i: usize) -> bool {
+        let (l, b) = (i / L::BITS as usize, i as u32 % L::BITS);
+        match self.limbs.get(l) {
+            Some(&x) => (x >> b) & L::ONE == L::ONE,
+            None => false,
+        }
+    }
+
+    pub fn set_bit(&mut self, i: usize, value: bool) {
+        let (l, b) = (i / L::BITS as usize, i as u32 % L::BITS);
+        if value {
+            if l >= self.limbs.len() {
+                self.limbs.resize(l + 1, L::ZERO);
+            }
+            self.limbs[l] = self.limbs[l] | (L::ONE << b);
+        } else if l < self.limbs.len() {
+            self.limbs[l] = self.limbs[l] & !(L::ONE << b);
+            limbs::normalize(&mut self.limbs);
+        }
+    }
+
+    pub fn significant_bits(&self) -> usize {
+        match self.limbs.last() {
+            Some(&top) => self.limbs.len() * L::BITS as usize - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    pub fn trailing_zeros(&self) -> Option<usize> {
+        let l = self.limbs.iter().position(|&x| x != L::ZERO)?;
+        return Some(l * L::BITS as usize + self.limbs[l].trailing_zeros() as usize);
+    }
+
+    pub fn div_rem(&self, other: &Self) -> (Self, Self) {
+        let (q, r) = limbs::divrem(&self.limbs, &other.limbs);
+        return (NativeNatural { limbs: q }, NativeNatural { limbs: r });
+    }
+
+    // Lower case letters, like format!("{:x}") on the primitives.
+    pub fn to_str_radix(&self, radix: u32) -> String {
+        radix::to_str_radix(&self.limbs, radix)
+    }
+
+    // Little endian bytes, with no high zero bytes. Zero is empty.
+    pub fn from_bytes_le(bytes: &[u8]) -> Self {
+        let per = (L::BITS / 8) as usize;
+        let limbs = bytes.chunks(per)
+            .map(|c| c.iter().rev().fold(L::ZERO, |x, &b| (x << 8) | L::from_u64(b as u64)))
+            .collect();
+        let mut n = NativeNatural { limbs };
+        limbs::normalize(&mut n.limbs);
+        return n;
+    }
+
+    pub fn to_bytes_le(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.limbs.len() * (L::BITS / 8) as usize);
+        for &x in self.limbs.iter() {
+            for i in 0..L::BITS / 8 {
+                bytes.push((x >> (8 * i)).to_u64() as u8);
+            }
+        }
+        while bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+        return bytes;
+    }
+
+    // 64 bit limbs, least significant first, whatever L is.
+    pub fn from_u64_limbs(src: &[u64]) -> Self {
+        let mut limbs = Vec::with_capacity(src.len() * (64 / L::BITS) as usize);
+        for &w in src.iter() {
+            for j in 0..64 / L::BITS {
+                limbs.push(L::from_u64(w >> (j * L::BITS)));
+            }
+        }
+        limbs::normalize(&mut limbs);
+        return NativeNatural { limbs };
+    }
+
+    pub fn to_u64_limbs(&self) -> Vec<u64> {
+        let per = (64 / L::BITS) as usize;
+        return self.limbs.chunks(per)
+            .map(|c| c.iter().enumerate().fold(0, |w, (j, &x)| w | x.to_u64() << (j as u32 * L::BITS)))
+            .collect();
+    }
+}
+
+impl<L: Appendage> Display for NativeNatural<L> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.pad_integral(true, "", &self.to_str_radix(10))
+    }
+}
+
+impl<L: Appendage> Unsigned for NativeNatural<L> {}
+impl<L: Appendage> NotWrapped for NativeNatural<L> {}
+
+impl<L: Appendage> FromStrRadix for NativeNatural<L> {
+    fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseBigError> {
+        if !(2..=36).contains(&radix) {
+            return Err(ParseBigError {kind: LibError(format!("radix {} out of range", radix))});
+        }
+        let (negative, digits) = match src.as_bytes().first() {
+            Some(b'-') => (true, &src[1..]),
+            Some(b'+') => (false, &src[1..]),
+            _ => (false, src),
+        };
+        if digits.is_empty() {
+            return Err(ParseBigError {kind: LibError(String::from("no digits"))});
+        }
+        let (_, per_chunk) = radix::radix_chunk::<L>(radix);
+        let mut v: Vec<L> = Vec::new();
+        let mut acc: u64 = 0;
+        let mut scale: u64 = 1;
+        let mut count = 0;
+        for c in digits.chars() {
+            let d = match c.to_digit(radix) {
+                Some(d) => d,
+                None => return Err(ParseBigError {
+                    kind: LibError(format!("invalid digit {:?} for radix {}", c, radix))
+                }),
+            };
+            acc = acc * (radix as u64) + (d as u64);
+            scale *= radix as u64;
+            count += 1;
+            if count == per_chunk {
+                limbs::mul_small_add(&mut v, L::from_u64(scale), L::from_u64(acc));
+                acc = 0;
+                scale = 1;
+                count = 0;
+            }
+        }
+        if count > 0 {
+            limbs::mul_small_add(&mut v, L::from_u64(scale), L::from_u64(acc));
+        }
+        if negative && !v.is_empty() {
+            return Err(ParseBigError {kind: Underflow});
+        }
+        return Ok(NativeNatural { limbs: v });
+    }
+}
+
+macro_rules! native_from_unsigned {
+    ($U:ty) => {
+        impl<L: Appendage> From<$U> for NativeNatural<L> {
+            fn from(src: $U) -> Self {
+                return NativeNatural { limbs: limbs::from_u128(src as u128) };
+            }
+        }
+    }
+}
+
+native_from_unsigned!(u8);
+native_from_unsigned!(u16);
+native_from_unsigned!(u32);
+native_from_unsigned!(u64);
+native_from_unsigned!(u128);
+native_from_unsigned!(usize);
+
+impl<L: Appendage> PartialOrd for NativeNatural<L> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<L: Appendage> Ord for NativeNatural<L> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        limbs::cmp(&self.limbs, &other.limbs)
+    }
+}
+
+impl<L: Appendage> Add for NativeNatural<L> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        NativeNatural { limbs: limbs::add(&self.limbs, &other.limbs) }
+    }
+}
+
+impl<L: Appendage> AddAssign for NativeNatural<L> {
+    fn add_assign(&mut self, other: Self) {
+        self.limbs = limbs::add(&self.limbs, &other.limbs);
+    }
+}
+
+impl<L: Appendage> Sub for NativeNatural<L> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        match limbs::sub(&self.limbs, &other.limbs) {
+            Some(limbs) => NativeNatural { limbs },
+            None => panic!("Underflow"),
+        }
+    }
+}
+
+impl<L: Appendage> SubAssign for NativeNatural<L> {
+    fn sub_assign(&mut self, other: Self) {
+        match limbs::sub(&self.limbs, &other.limbs) {
+            Some(limbs) => self.limbs = limbs,
+            None => panic!("Underflow"),
+        }
+    }
+}
+
+impl<L: Appendage> Mul for NativeNatural<L> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        NativeNatural { limbs: limbs::mul(&self.limbs, &other.limbs) }
+    }
+}
+
+impl<L: Appendage> MulAssign for NativeNatural<L> {
+    fn mul_assign(&mut self, other: Self) {
+        self.limbs = limbs::mul(&self.limbs, &other.limbs);
+    }
+}
+
+impl<L: Appendage> Div for NativeNatural<L> {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        self.div_rem(&other).0
+    }
+}
+
+impl<L: Appendage> Rem for NativeNatural<L> {
+    type Output = Self;
+
+    fn rem(self, other: Self) -> Self {
+        self.div_rem(&other).1
+    }
+}
+
+impl<L: Appendage> BitAnd for NativeNatural<L> {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        NativeNatural { limbs: limbs::and(&self.limbs, &other.limbs) }
+    }
+}
+
+impl<L: Appendage> Shl<usize> for NativeNatural<L> {
+    type Output = Self;
+
+    fn shl(self, bits: usize) -> Self {
+        NativeNatural { limbs: limbs::shl(&self.limbs, bits) }
+    }
+}
+
+impl<L: Appendage> Shr<usize> for NativeNatural<L> {
+    type Output = Self;
+
+    fn shr(self, bits: usize) -> Self {
+        NativeNatural { limbs: limbs::shr(&self.limbs, bits) }
+    }
+}
+
+// Arithmetic with primitives goes through a NativeNatural of the
+// primitive.
+macro_rules! native_arithmetic_with {
+    ($U:ty) => {
+        impl<L: Appendage> Add<$U> for NativeNatural<L> {
+            type Output = Self;
+
+            fn add(self, other: $U) -> Self {
+                self + Self::from(other)
+            }
+        }
+
+        impl<L: Appendage> AddAssign<$U> for NativeNatural<L> {
+            fn add_assign(&mut self, other: $U) {
+                *self += Self::from(other);
+            }
+        }
+
+        impl<L: Appendage> Sub<$U> for NativeNatural<L> {
+            type Output = Self;
+
+            fn sub(self, other: $U) -> Self {
+                self - Self::from(other)
+            }
+        }
+
+        impl<L: Appendage> SubAssign<$U> for NativeNatural<L> {
+            fn sub_assign(&mut self, other: $U) {
+                *self -= Self::from(other);
+            }
+        }
+
+        impl<L: Appendage> Mul<$U> for NativeNatural<L> {
+            type Output = Self;
+
+            fn mul(self, other: $U) -> Self {
+                self * Self::from(other)
+            }
+        }
+
+        impl<L: Appendage> MulAssign<$U> for NativeNatural<L> {
+            fn mul_assign(&mut self, other: $U) {
+                *self *= Self::from(other);
+            }
+        }
+
+        impl<L: Appendage> Div<$U> for NativeNatural<L> {
+            type Output = Self;
+
+            fn div(self, other: $U) -> Self {
+                self / Self::from(other)
+            }
+        }
+
+        impl<L: Appendage> Rem<$U> for NativeNatural<L> {
+            type Output = Self;
+
+            fn rem(self, other: $U) -> Self {
+                self % Self::from(other)
+            }
+        }
+
+        impl<L: Appendage> BitAnd<$U> for NativeNatural<L> {
+            type Output = Self;
+
+            fn bitand(self, other: $U) -> Self {
+                self & Self::from(other)
+            }
+        }
+    }
+}
+
+native_arithmetic_with!(u8);
+native_arithmetic_with!(u16);
+native_arithmetic_with!(u32);
+native_arithmetic_with!(u64);
+native_arithmetic_with!(u128);
+native_arithmetic_with!(usize);
+
+impl<L: Appendage> TryAdd<NativeNatural<L>> for NativeNatural<L> {
+    fn try_add(&self, other: NativeNatural<L>) -> Option<Self> {
+        Some(NativeNatural { limbs: limbs::add(&self.limbs, &other.limbs) })
+    }
+}
+
+impl<L: Appendage> TrySub<NativeNatural<L>> for NativeNatural<L> {
+    fn try_sub(&self, other: NativeNatural<L>) -> Option<Self> {
+        limbs::sub(&self.limbs, &other.limbs).map(|limbs| NativeNatural { limbs })
+    }
+}
+
+macro_rules! native_try_unsigned {
+    ($U:ty) => {
+        impl<L: Appendage> TryAdd<$U> for NativeNatural<L> {
+            fn try_add(&self, other: $U) -> Option<Self> {
+                self.try_add(Self::from(other))
+            }
+        }
+
+        impl<L: Appendage> TrySub<$U> for NativeNatural<L> {
+            fn try_sub(&self, other: $U) -> Option<Self> {
+                self.try_sub(Self::from(other))
+            }
+        }
+    }
+}
+
+// Adding a negative number is subtracting its magnitude and vice versa.
+macro_rules! native_try_signed {
+    ($U:ty) => {
+        impl<L: Appendage> TryAdd<$U> for NativeNatural<L> {
+            fn try_add(&self, other: $U) -> Option<Self> {
+                let m = Self::from((other as i128).unsigned_abs());
+                match other < 0 {
+                    true => self.try_sub(m),
+                    false => self.try_add(m),
+                }
+            }
+        }
+
+        impl<L: Appendage> TrySub<$U> for NativeNatural<L> {
+            fn try_sub(&self, other: $U) -> Option<Self> {
+                let m = Self::from((other as i128).unsigned_abs());
+                match other < 0 {
+                    true => self.try_add(m),
+                    false => self.try_sub(m),
+                }
+            }
+        }
+    }
+}
+
+native_try_unsigned!(u8);
+native_try_unsigned!(u16);
+native_try_unsigned!(u32);
+native_try_unsigned!(u64);
+native_try_unsigned!(u128);
+native_try_unsigned!(usize);
+native_try_signed!(i8);
+native_try_signed!(i16);
+native_try_signed!(i32);
+native_try_signed!(i64);
+native_try_signed!(i128);
+native_try_signed!(isize);
+
+// The WrappedNatural side is all macros over a concrete type, so it's
+// stamped out once per limb size. Backends generic over a constant can
+// pass [generics] first and get one generic impl instead.
+macro_rules! native_natural {
+    ([$($g:tt)*] $Y:ty) => {
+        natural_from_unsigned!([$($g)*] $Y, u8);
+        natural_from_unsigned!([$($g)*] $Y, u16);
+        natural_from_unsigned!([$($g)*] $Y, u32);
+        natural_from_unsigned!([$($g)*] $Y, u64);
+        natural_from_unsigned!([$($g)*] $Y, u128);
+        natural_from_unsigned!([$($g)*] $Y, usize);
+        natural_from_unsigned!([$($g)*] $Y, $Y);
+
+        arithmetic_with_unsigned!([$($g)*] $Y, u8);
+        arithmetic_with_unsigned!([$($g)*] $Y, u16);
+        arithmetic_with_unsigned!([$($g)*] $Y, u32);
+        arithmetic_with_unsigned!([$($g)*] $Y, u64);
+        arithmetic_with_unsigned!([$($g)*] $Y, u128);
+        arithmetic_with_unsigned!([$($g)*] $Y, usize);
+        arithmetic_with_unsigned!([$($g)*] $Y, $Y);
+        arithmetic_with_self!([$($g)*] $Y);
+        shift_ops!([$($g)*] $Y, usize);
+
+        checked_with_all_primitives!([$($g)*] $Y);
+        checked_with!([$($g)*] $Y, $Y);
+        checked_with_self!([$($g)*] $Y);
+
+        impl<$($g)*> Natural for WrappedNatural<$Y> {
+            fn div_rem(self, other: Self) -> (Self, Self) {
+                let (q, r) = (self.0).div_rem(&other.0);
+                return (Self(q), Self(r));
+            }
+
+            fn bit(&self, i: usize) -> bool {
+                (self.0).bit(i)
+            }
+
+            fn set_bit(&mut self, i: usize, value: bool) {
+                (self.0).set_bit(i, value);
+            }
+
+            fn significant_bits(&self) -> usize {
+                (self.0).significant_bits()
+            }
+
+            fn trailing_zeros(&self) -> Option<usize> {
+                (self.0).trailing_zeros()
+            }
+
+            fn to_str_radix(&self, radix: u32) -> String {
+                (self.0).to_str_radix(radix)
+            }
+
+            fn from_bytes_le(bytes: &[u8]) -> Self {
+                WrappedNatural(<$Y>::from_bytes_le(bytes))
+            }
+
+            fn to_bytes_le(&self) -> Vec<u8> {
+                (self.0).to_bytes_le()
+            }
+
+            fn from_u64_limbs(limbs: &[u64]) -> Self {
+                WrappedNatural(<$Y>::from_u64_limbs(limbs))
+            }
+
+            fn to_u64_limbs(&self) -> Vec<u64> {
+                (self.0).to_u64_limbs()
+            }
+        }
+    };
+    ($Y:ty) => {
+        native_natural!([] $Y);
+    };
+}
+
+native_natural!(NativeNatural32);
+native_natural!(NativeNatural64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn n(s: &str) -> NativeNatural {
+        NativeNatural::from_str_radix(s, 10).unwrap()
+    }
+
+    #[test]
+    fn parse_and_print() {
+        let s = "943123456789012345678901234567890123456789";
+        assert_eq!(n(s).to_str_radix(10), s);
+        assert_eq!(n("0").to_str_radix(10), "0");
+        assert_eq!(n("+17").to_str_radix(10), "17");
+        let h = NativeNatural64::from_str_radix("DeadBeef0123456789abcdef", 16).unwrap();
+        assert_eq!(h.to_str_radix(16), "deadbeef0123456789abcdef");
+        assert!(NativeNatural64::from_str_radix("12x", 10).is_err());
+        assert!(NativeNatural64::from_str_radix("", 10).is_err());
+        assert!(NativeNatural64::from_str_radix("-5", 10).is_err());
+        assert!(WrappedNatural::<NativeNatural>::from_str("-5").is_err());
+        let t = NativeNatural32::from_str_radix(s, 10).unwrap();
+        assert_eq!(t.to_str_radix(10), s);
+        assert_eq!(t.limbs().len(), 5);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = n("340282366920938463463374607431768211455"); // 2^128 - 1
+        assert_eq!((a.clone() + 1u8).to_str_radix(16), "100000000000000000000000000000000");
+        assert_eq!(a.clone() - a.clone(), NativeNatural::zero());
+        let sq = a.clone() * a.clone();
+        assert_eq!(sq.div_rem(&a), (a.clone(), NativeNatural::zero()));
+        assert_eq!((sq.clone() + 5u32) % a.clone(), NativeNatural::from(5u8));
+        assert_eq!((a.clone() << 3) >> 3, a);
+        assert!(n("10") < n("11"));
+        let w = WrappedNatural(a.clone()) + 1usize - 1u64;
+        assert_eq!(w.0, a);
+    }
+
+    #[test]
+    #[should_panic(expected = "Underflow")]
+    fn sub_underflow() {
+        let _ = WrappedNatural(n("3")) - 4u8;
+    }
+}
+
+#[cfg(all(test, feature="rug_integer"))]
+mod vs_rug {
+    use super::*;
+    use rug::Integer as RugInteger;
+
+    fn both<L: Appendage>(s: &str) -> (NativeNatural<L>, RugInteger) {
+        (
+            NativeNatural::from_str_radix(s, 10).unwrap(),
+            RugInteger::from_str_radix(s, 10).unwrap(),
+        )
+    }
+
+    fn same<L: Appendage>(a: &NativeNatural<L>, b: &RugInteger) {
+        assert_eq!(a.to_str_radix(16), b.to_string_radix(16));
+    }
+
+    fn agree<L: Appendage>() {
+        let mut x = String::from("1");
+        let mut y = String::from("7");
+        for i in 0..400 {
+            x.push(std::char::from_digit((i * 7 % 10) as u32, 10).unwrap());
+            if i % 3 == 0 {
+                y.push(std::char::from_digit((i * 3 % 10) as u32, 10).unwrap());
+            }
+            let (an, ar) = both::<L>(&x);
+            let (bn, br) = both::<L>(&y);
+            same(&(an.clone() + bn.clone()), &RugInteger::from(&ar + &br));
+            same(&(an.clone() * bn.clone()), &RugInteger::from(&ar * &br));
+            let (q, r) = an.div_rem(&bn);
+            same(&q, &RugInteger::from(&ar / &br));
+            same(&r, &RugInteger::from(&ar % &br));
+            if an >= bn {
+                same(&(an.clone() - bn.clone()), &RugInteger::from(&ar - &br));
+            }
+            same(&(an.clone() << i), &RugInteger::from(&ar << i as u32));
+            same(&(an.clone() >> (i / 2)), &RugInteger::from(&ar >> (i / 2) as u32));
+        }
+    }
+
+    #[test]
+    fn backends_agree() {
+        agree::<u64>();
+        agree::<u32>();
+    }
+}
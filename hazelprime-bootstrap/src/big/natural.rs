@@ -6,15 +6,32 @@ macro_rules! use_arith {
         use std::ops::AddAssign;
         use std::ops::Sub;
         use std::ops::SubAssign;
+        use std::ops::Mul;
+        use std::ops::MulAssign;
+        use std::ops::Div;
+        use std::ops::Rem;
+        use std::ops::BitAnd;
+        use std::ops::Shl;
+        use std::ops::Shr;
     }
 }
 
+use std::convert::TryFrom;
 use std::str::FromStr;
+use std::fmt::Display;
+use std::fmt::LowerHex;
+use std::fmt::UpperHex;
+use std::fmt::Binary;
+use std::fmt::Octal;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
 use_arith!();
 
 use super::parse::{*};
 use super::sign::{*};
+use super::bits::{Bits, Windows};
 
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct WrappedNatural<T>(pub(super) T);
 
 pub trait NotWrapped {}
@@ -48,6 +65,35 @@ impl <T: FromStrRadix> FromStrRadix for WrappedNatural<T>
     }
 }
 
+impl<T: Display> Display for WrappedNatural<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        self.0.fmt(f)
+    }
+}
+
+// The other radixes go through Natural::to_str_radix, with the usual
+// prefixes for {:#x} and friends.
+macro_rules! radix_fmt {
+    ($F:ident, $radix:expr, $prefix:expr, $upper:expr) => {
+        impl<T> $F for WrappedNatural<T> where
+            WrappedNatural<T>: Natural,
+        {
+            fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+                let mut s = self.to_str_radix($radix);
+                if $upper {
+                    s.make_ascii_uppercase();
+                }
+                f.pad_integral(true, $prefix, &s)
+            }
+        }
+    }
+}
+
+radix_fmt!(LowerHex, 16, "0x", false);
+radix_fmt!(UpperHex, 16, "0x", true);
+radix_fmt!(Binary, 2, "0b", false);
+radix_fmt!(Octal, 8, "0o", false);
+
 impl<T: FromStrRadix> FromStr for WrappedNatural<T> 
     where T: IsNegative
 {
@@ -73,24 +119,29 @@ macro_rules! natural_from {
     }
 }
 
+// The macros a backend calls also take [generics] before the type, for
+// backends generic over something like a limb count.
 macro_rules! natural_from_unsigned {
-    ($Y:ident, $U:ty) => {
-        impl From<$U> for WrappedNatural<$Y> where
+    ([$($g:tt)*] $Y:ty, $U:ty) => {
+        impl<$($g)*> From<$U> for WrappedNatural<$Y> where
             $Y: From<$U>
         {
             fn from(src: $U) -> Self {
-                let newv: $Y = $Y::from(src);
+                let newv: $Y = <$Y>::from(src);
                 return Self(newv);
             }
         }
-    }
+    };
+    ($Y:ty, $U:ty) => {
+        natural_from_unsigned!([] $Y, $U);
+    };
 }
 
 macro_rules! plain_op {
-    ($T:ident, $f:ident, $Y:ty, $U:ty, $UT:path, $VT:path, $Vf:ident) => {
-        impl $T<$U> for WrappedNatural<$Y> where
+    ($T:ident, $f:ident, [$($g:tt)*] $Y:ty, $U:ty, $UT:path, $VT:path, $Vf:ident) => {
+        impl<$($g)*> $T<$U> for WrappedNatural<$Y> where
             $U: $UT,
-            $Y: $T<$U>,
+            $Y: $T<$U, Output = $Y>,
             $Y: $VT,
         {
             type Output = Self;
@@ -105,8 +156,8 @@ macro_rules! plain_op {
 }
 
 macro_rules! assign_op {
-    ($T:ident, $f:ident, $Y:ty, $U:ty, $UT:path, $VT:path, $Vf:ident) => {
-        impl $T<$U> for WrappedNatural<$Y> where 
+    ($T:ident, $f:ident, [$($g:tt)*] $Y:ty, $U:ty, $UT:path, $VT:path, $Vf:ident) => {
+        impl<$($g)*> $T<$U> for WrappedNatural<$Y> where 
             $U: $UT,
             $Y: $T<$U>,
             $Y: $VT,
@@ -120,56 +171,99 @@ macro_rules! assign_op {
 }
 
 macro_rules! plain_op_unchecked {
-    ($T:ident, $f:ident, $Y:ty, $U:ty) => {
-        plain_op!($T, $f, $Y, $U, Unsigned, NotWrapped, noop);
+    ($T:ident, $f:ident, [$($g:tt)*] $Y:ty, $U:ty) => {
+        plain_op!($T, $f, [$($g)*] $Y, $U, Unsigned, NotWrapped, noop);
     }
 }
 
 macro_rules! plain_op_checked {
-    ($T:ident, $f:ident, $Y:ty, $U:ty) => {
-        plain_op!($T, $f, $Y, $U, NotWrapped, AssertNotNegative, assert_not_negative);
+    ($T:ident, $f:ident, [$($g:tt)*] $Y:ty, $U:ty) => {
+        plain_op!($T, $f, [$($g)*] $Y, $U, NotWrapped, AssertNotNegative, assert_not_negative);
     }
 }
 
 macro_rules! assign_op_unchecked {
-    ($T:ident, $f:ident, $Y:ty, $U:ty) => {
-        assign_op!($T, $f, $Y, $U, Unsigned, NotWrapped, noop);
+    ($T:ident, $f:ident, [$($g:tt)*] $Y:ty, $U:ty) => {
+        assign_op!($T, $f, [$($g)*] $Y, $U, Unsigned, NotWrapped, noop);
     }
 }
 
 
 macro_rules! assign_op_checked {
-    ($T:ident, $f:ident, $Y:ty, $U:ty) => {
-        assign_op!($T, $f, $Y, $U, NotWrapped, AssertNotNegative, assert_not_negative);
+    ($T:ident, $f:ident, [$($g:tt)*] $Y:ty, $U:ty) => {
+        assign_op!($T, $f, [$($g)*] $Y, $U, NotWrapped, AssertNotNegative, assert_not_negative);
     }
 }
 
 macro_rules! sub_with_whatever {
-    ($Y:ty, $U:ty) => {
-        plain_op_checked!(Sub, sub, $Y, $U);
-        assign_op_checked!(SubAssign, sub_assign, $Y, $U);
+    ([$($g:tt)*] $Y:ty, $U:ty) => {
+        plain_op_checked!(Sub, sub, [$($g)*] $Y, $U);
+        assign_op_checked!(SubAssign, sub_assign, [$($g)*] $Y, $U);
     }
 }
 
 macro_rules! arithmetic_with_unsigned {
+    ([$($g:tt)*] $Y:ty, $U:ty) => {
+        plain_op_unchecked!(Add, add, [$($g)*] $Y, $U);
+        assign_op_unchecked!(AddAssign, add_assign, [$($g)*] $Y, $U);
+        sub_with_whatever!([$($g)*] $Y, $U);
+        plain_op_unchecked!(Mul, mul, [$($g)*] $Y, $U);
+        assign_op_unchecked!(MulAssign, mul_assign, [$($g)*] $Y, $U);
+        plain_op_unchecked!(Div, div, [$($g)*] $Y, $U);
+        plain_op_unchecked!(Rem, rem, [$($g)*] $Y, $U);
+        plain_op_unchecked!(BitAnd, bitand, [$($g)*] $Y, $U);
+    };
     ($Y:ty, $U:ty) => {
-        plain_op_unchecked!(Add, add, $Y, $U);
-        assign_op_unchecked!(AddAssign, add_assign, $Y, $U);
-        sub_with_whatever!($Y, $U);
-    }
+        arithmetic_with_unsigned!([] $Y, $U);
+    };
 }
 
 macro_rules! arithmetic_with_signed {
     ($Y:ident, $U:ty) => {
-        plain_op_checked!(Add, add, $Y, $U);
-        assign_op_checked!(AddAssign, add_assign, $Y, $U);
-        sub_with_whatever!($Y, $U);
+        plain_op_checked!(Add, add, [] $Y, $U);
+        assign_op_checked!(AddAssign, add_assign, [] $Y, $U);
+        sub_with_whatever!([] $Y, $U);
+        plain_op_checked!(Mul, mul, [] $Y, $U);
+        assign_op_checked!(MulAssign, mul_assign, [] $Y, $U);
+        plain_op_checked!(Div, div, [] $Y, $U);
+        plain_op_checked!(Rem, rem, [] $Y, $U);
+        plain_op_checked!(BitAnd, bitand, [] $Y, $U);
     }
 }
 
+// Converts a shift count to the backend's type, panicking rather than
+// wrapping around into a different shift when it doesn't fit.
+pub fn shift_count<S: TryFrom<usize>>(bits: usize) -> S {
+    S::try_from(bits).unwrap_or_else(|_| panic!("shift too large: {}", bits))
+}
+
+// Shifts take a bit count, converted to whatever $S the backend wants.
+macro_rules! shift_ops {
+    ([$($g:tt)*] $Y:ty, $S:ty) => {
+        impl<$($g)*> Shl<usize> for WrappedNatural<$Y> {
+            type Output = Self;
+
+            fn shl(self, bits: usize) -> Self::Output {
+                Self((self.0).shl(shift_count::<$S>(bits)))
+            }
+        }
+
+        impl<$($g)*> Shr<usize> for WrappedNatural<$Y> {
+            type Output = Self;
+
+            fn shr(self, bits: usize) -> Self::Output {
+                Self((self.0).shr(shift_count::<$S>(bits)))
+            }
+        }
+    };
+    ($Y:ty, $S:ty) => {
+        shift_ops!([] $Y, $S);
+    };
+}
+
 macro_rules! arithmetic_with_self {
-    ($Y:ty) => {
-        impl Add for WrappedNatural<$Y> where
+    ([$($g:tt)*] $Y:ty) => {
+        impl<$($g)*> Add for WrappedNatural<$Y> where
         {
             type Output = Self;
             
@@ -178,7 +272,7 @@ macro_rules! arithmetic_with_self {
             }
         }
 
-        impl AddAssign for WrappedNatural<$Y> where
+        impl<$($g)*> AddAssign for WrappedNatural<$Y> where
             WrappedNatural<$Y>: AddAssign<$Y>
         {
             fn add_assign(&mut self, other: Self) {
@@ -186,7 +280,7 @@ macro_rules! arithmetic_with_self {
             }
         }
         
-        impl Sub for WrappedNatural<$Y> where
+        impl<$($g)*> Sub for WrappedNatural<$Y> where
         {
             type Output = Self;
             
@@ -195,14 +289,60 @@ macro_rules! arithmetic_with_self {
             }
         }
 
-        impl SubAssign for WrappedNatural<$Y> where
+        impl<$($g)*> SubAssign for WrappedNatural<$Y> where
             WrappedNatural<$Y>: AddAssign<$Y>
         {
             fn sub_assign(&mut self, other: Self) {
                 self.sub_assign(other.0);
             }
         }
-    }
+
+        impl<$($g)*> Mul for WrappedNatural<$Y> where
+        {
+            type Output = Self;
+
+            fn mul(self, other: Self) -> Self::Output {
+                self * other.0
+            }
+        }
+
+        impl<$($g)*> MulAssign for WrappedNatural<$Y> where
+        {
+            fn mul_assign(&mut self, other: Self) {
+                self.mul_assign(other.0);
+            }
+        }
+
+        impl<$($g)*> Div for WrappedNatural<$Y> where
+        {
+            type Output = Self;
+
+            fn div(self, other: Self) -> Self::Output {
+                self / other.0
+            }
+        }
+
+        impl<$($g)*> Rem for WrappedNatural<$Y> where
+        {
+            type Output = Self;
+
+            fn rem(self, other: Self) -> Self::Output {
+                self % other.0
+            }
+        }
+
+        impl<$($g)*> BitAnd for WrappedNatural<$Y> where
+        {
+            type Output = Self;
+
+            fn bitand(self, other: Self) -> Self::Output {
+                self & other.0
+            }
+        }
+    };
+    ($Y:ty) => {
+        arithmetic_with_self!([] $Y);
+    };
 }
 
 // impl<T, U> Add<WrappedNatural<U>> for WrappedNatural<T> where
@@ -218,24 +358,177 @@ macro_rules! arithmetic_with_self {
 //     }
 // }
 
+// $R are the operators producing a new value, $A the assigning ones.
 macro_rules! make_natural_trait_ops {
-    ($d:tt $($R:ident),*) => {
+    ($d:tt [$($R:ident),*] [$($A:ident),*]) => {
         macro_rules! make_natural_trait_types {
             ($d($d Y:ty),*) => {
-                pub trait Natural: FromStrRadix + FromStr
-                    $(+ $R)*
+                pub trait Natural: FromStrRadix + FromStr + Clone + Ord + Display
+                    + Shl<usize, Output = Self> + Shr<usize, Output = Self>
+                    $(+ $R<Output = Self>)*
+                    $(+ $A)*
                     $d(
                         + From<$Y>
-                        $(+ $R<$Y>)*
+                        $(+ $R<$Y, Output = Self>)*
+                        $(+ $A<$Y>)*
                     )*
-                {}
+                {
+                    // Quotient and remainder at once, rounding towards zero.
+                    fn div_rem(self, other: Self) -> (Self, Self);
+
+                    // Bit i, counting from the least significant.
+                    fn bit(&self, i: usize) -> bool;
+
+                    fn set_bit(&mut self, i: usize, value: bool);
+
+                    // Bits up to and including the top set one, 0 for zero.
+                    fn significant_bits(&self) -> usize;
+
+                    // The index of the lowest set bit, None for zero.
+                    fn trailing_zeros(&self) -> Option<usize>;
+
+                    // In radix 2 to 36, with lower case letters.
+                    fn to_str_radix(&self, radix: u32) -> String;
+
+                    // Bytes and 64 bit limbs, for checkpoints and other
+                    // tools. Exports have no high zeros, so zero is empty.
+                    fn from_bytes_le(bytes: &[u8]) -> Self;
+                    fn to_bytes_le(&self) -> Vec<u8>;
+                    fn from_u64_limbs(limbs: &[u64]) -> Self;
+                    fn to_u64_limbs(&self) -> Vec<u64>;
+
+                    fn from_bytes_be(bytes: &[u8]) -> Self {
+                        let le: Vec<u8> = bytes.iter().rev().cloned().collect();
+                        Self::from_bytes_le(&le)
+                    }
+
+                    fn to_bytes_be(&self) -> Vec<u8> {
+                        let mut bytes = self.to_bytes_le();
+                        bytes.reverse();
+                        bytes
+                    }
+
+                    fn bits(&self) -> Bits<'_, Self> {
+                        Bits::new(self)
+                    }
+
+                    fn windows(&self, width: u32) -> Windows<'_, Self> {
+                        Windows::new(self, width)
+                    }
+                }
             }
         }
     }
 }
 
-make_natural_trait_ops! {$ Add, AddAssign, Sub, SubAssign}
+make_natural_trait_ops! {$ [Add, Sub, Mul, Div, Rem, BitAnd] [AddAssign, SubAssign, MulAssign]}
 make_natural_trait_types! {u8, u16, u32, u64, u128, usize}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::testing::backend_tests;
+
+    backend_tests!(ops, bits, radix, bytes);
 
+    fn ops<T: Natural + std::fmt::Debug>() {
+        let a = T::from_str("123456789012345678901234567890").ok().unwrap();
+        let b = T::from(987654321u32);
+        let p = T::from_str("121932631124828532112482853211126352690").ok().unwrap();
+        let a2 = a.clone();
+        let prod = a * b;
+        assert!(prod == p);
+        let (q, r) = prod.div_rem(T::from(987654321u64));
+        assert_eq!(q.to_string(), "123456789012345678901234567890");
+        assert_eq!(r.to_string(), "0");
+        assert!(q == a2);
+        let x = T::from(1000u16) * 3u8 / 7u8;
+        assert_eq!(x.to_string(), "428");
+        let y = T::from(1000u16) % 7u32 + 1usize;
+        assert_eq!(y.to_string(), "7");
+        let z = (T::from(0xf0f0u32) & T::from(0xff00u32)) << 4;
+        assert_eq!(z.to_string(), "983040");
+        assert_eq!((z >> 8).to_string(), "3840");
+        let mut m = T::from(3u8);
+        m *= 5u8;
+        m *= T::from(2u8);
+        assert_eq!(m.to_string(), "30");
+        assert!(T::from(2u8) < T::from(3u8));
+    }
+
+    fn bits<T: Natural + std::fmt::Debug>() {
+        let b = T::from(0b1011_0000u8);
+        assert!(b.bit(4) && !b.bit(6) && b.bit(7) && !b.bit(1000));
+        assert_eq!(b.significant_bits(), 8);
+        assert_eq!(b.trailing_zeros(), Some(4));
+        assert_eq!(T::from(0u8).significant_bits(), 0);
+        assert_eq!(T::from(0u8).trailing_zeros(), None);
+        assert!(!T::from(1u8).bit(1 << 32));
+        let forward: Vec<bool> = b.bits().collect();
+        assert_eq!(forward, vec![false, false, false, false, true, true, false, true]);
+        let backward: Vec<bool> = b.bits().rev().collect();
+        assert_eq!(backward, vec![true, false, true, true, false, false, false, false]);
+        let mut bits = b.bits();
+        assert_eq!((bits.next(), bits.next_back(), bits.len()), (Some(false), Some(true), 6));
+
+        let mut c = T::from(1u8) << 200;
+        assert_eq!(c.significant_bits(), 201);
+        assert_eq!(c.trailing_zeros(), Some(200));
+        c.set_bit(3, true);
+        c.set_bit(130, true);
+        c.set_bit(200, false);
+        assert_eq!(c, (T::from(1u8) << 130) + 8u8);
+        c.set_bit(130, false);
+        c.set_bit(500, false);
+        assert_eq!(c, T::from(8u8));
+        let windows: Vec<(usize, u64)> = ((T::from(1u8) << 130) + 0x5_0000_0003u64).windows(16).collect();
+        assert_eq!(windows, vec![(0, 3), (32, 5), (128, 4)]);
+        let windows: Vec<(usize, u64)> = T::from(0xffu8).windows(3).rev().collect();
+        assert_eq!(windows, vec![(6, 3), (3, 7), (0, 7)]);
+        assert_eq!(T::from(0u8).windows(5).next(), None);
+    }
+
+    fn radix<T: Natural + std::fmt::Debug + LowerHex + UpperHex + Binary + Octal>() {
+        let a = T::from_str("123456789012345678901234567890").ok().unwrap();
+        assert_eq!(a.to_string(), "123456789012345678901234567890");
+        let h = T::from(0xbeefu32) << 64;
+        assert_eq!(h.to_str_radix(16), "beef0000000000000000");
+        assert_eq!(format!("{:x}", h), "beef0000000000000000");
+        assert_eq!(format!("{:#X}", h), "0xBEEF0000000000000000");
+        assert_eq!(format!("{:b}", T::from(10u8)), "1010");
+        assert_eq!(format!("{:#o}", T::from(8u8)), "0o10");
+        assert_eq!(format!("{:>6}", T::from(42u8)), "    42");
+        assert_eq!(format!("{:08x}", T::from(255u8)), "000000ff");
+        assert_eq!(T::from(35u8).to_str_radix(36), "z");
+        let big = T::from(7u8) << 5000;
+        assert_eq!(T::from_str_radix(&big.to_str_radix(10), 10).ok().unwrap(), big);
+        assert_eq!(T::from_str_radix(&big.to_str_radix(3), 3).ok().unwrap(), big);
+    }
+
+    fn bytes<T: Natural + std::fmt::Debug>() {
+        let n = T::from(0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10u128);
+        let le: Vec<u8> = (1..=16).rev().collect();
+        assert_eq!(n.to_bytes_le(), le);
+        assert_eq!(n.to_bytes_be(), (1..=16).collect::<Vec<u8>>());
+        assert_eq!(T::from_bytes_le(&le), n);
+        assert_eq!(T::from_bytes_be(&[0, 0, 1, 2]), T::from(0x102u16));
+        assert_eq!(T::from_bytes_le(&[1, 2, 0, 0]).to_bytes_le(), vec![1, 2]);
+        assert_eq!(n.to_u64_limbs(), vec![0x090a_0b0c_0d0e_0f10, 0x0102_0304_0506_0708]);
+        assert_eq!(T::from_u64_limbs(&[5, 0, 0]), T::from(5u8));
+        assert_eq!(T::from(0u8).to_bytes_le(), Vec::<u8>::new());
+        assert_eq!(T::from(0u8).to_u64_limbs(), Vec::<u64>::new());
+        assert_eq!(T::from_bytes_be(&[]), T::from(0u8));
+        let odd = (T::from(0xabu8) << 4000) + 0x1234_5678u32;
+        assert_eq!(odd.to_bytes_be().len(), 501);
+        assert_eq!(T::from_bytes_be(&odd.to_bytes_be()), odd);
+        assert_eq!(T::from_bytes_le(&odd.to_bytes_le()), odd);
+        assert_eq!(T::from_u64_limbs(&odd.to_u64_limbs()), odd);
+    }
 
+    #[cfg(feature="rug_integer")]
+    #[test]
+    #[should_panic(expected = "bit index too large")]
+    fn rug_integer_set_bit_too_large() {
+        WrappedNatural::<rug::Integer>::from(1u8).set_bit(1 << 32, true);
+    }
+}
@@ -0,0 +1,171 @@
+#![warn(rust_2018_idioms)]
+
+// Turning limbs into digits. Small numbers get repeated division by the
+// biggest power of the radix that fits in a limb. Big ones are split in
+// half by a precomputed power of the radix, recursively, so that the
+// cost is a few multiplications per level instead of quadratic.
+
+use std::cmp::Ordering;
+
+use super::limbs;
+use crate::types::Appendage;
+
+// Below this many limbs the quadratic conversion wins.
+pub const DC_THRESHOLD: usize = 30;
+
+// The biggest power of radix that fits in a limb, and its exponent.
+pub fn radix_chunk<L: Appendage>(radix: u32) -> (u64, usize) {
+    let max = L::MAX.to_u64();
+    let mut chunk: u64 = radix as u64;
+    let mut digits = 1;
+    while let Some(next) = chunk.checked_mul(radix as u64) {
+        if next > max {
+            break;
+        }
+        chunk = next;
+        digits += 1;
+    }
+    return (chunk, digits);
+}
+
+fn basecase<L: Appendage>(a: &[L], radix: u32) -> String {
+    if a.is_empty() {
+        return String::from("0");
+    }
+    let (chunk, digits) = radix_chunk::<L>(radix);
+    let mut v = a.to_vec();
+    let mut chunks: Vec<u64> = Vec::new();
+    while !v.is_empty() {
+        chunks.push(limbs::divrem_small(&mut v, L::from_u64(chunk)).to_u64());
+    }
+    let mut s = String::new();
+    for (i, c) in chunks.iter().rev().enumerate() {
+        let mut d = Vec::with_capacity(digits);
+        let mut c = *c;
+        while c > 0 {
+            d.push(std::char::from_digit((c % (radix as u64)) as u32, radix).unwrap());
+            c /= radix as u64;
+        }
+        if i > 0 {
+            while d.len() < digits {
+                d.push('0');
+            }
+        }
+        s.extend(d.iter().rev());
+    }
+    return s;
+}
+
+// radix^(digits * 2^k) for each level k, with reciprocals for the ones
+// big enough to be worth it.
+struct Powers<L> {
+    radix: u32,
+    levels: Vec<Level<L>>,
+}
+
+struct Level<L> {
+    p: Vec<L>,
+    digits: usize,
+    recip: Option<Vec<L>>,
+}
+
+impl<L: Appendage> Powers<L> {
+    // Enough levels that the top one squared is bigger than a.
+    fn new(a: &[L], radix: u32) -> Self {
+        let (chunk, digits) = radix_chunk::<L>(radix);
+        let mut levels = vec![Level {p: limbs::from_u128(chunk as u128), digits, recip: None}];
+        loop {
+            let top = levels.last().unwrap();
+            if 2 * top.p.len() - 1 > a.len() {
+                break;
+            }
+            let p = limbs::mul(&top.p, &top.p);
+            let digits = 2 * top.digits;
+            let recip = if p.len() > limbs::RECIPROCAL_THRESHOLD {
+                Some(limbs::reciprocal(&p))
+            } else {
+                None
+            };
+            levels.push(Level {p, digits, recip});
+        }
+        return Powers {radix, levels};
+    }
+
+    // Appends x to out, left padded with zeros to width digits if given.
+    fn convert(&self, x: &[L], mut k: usize, width: Option<usize>, out: &mut String) {
+        if x.len() < DC_THRESHOLD {
+            let s = basecase(x, self.radix);
+            if let Some(w) = width {
+                for _ in s.len()..w {
+                    out.push('0');
+                }
+            }
+            out.push_str(&s);
+            return;
+        }
+        while k > 0 && limbs::cmp(&self.levels[k].p, x) == Ordering::Greater {
+            k -= 1;
+        }
+        let level = &self.levels[k];
+        let (q, r) = match &level.recip {
+            Some(recip) => limbs::divrem_reciprocal(x, &level.p, recip),
+            None => limbs::divrem(x, &level.p),
+        };
+        self.convert(&q, k, width.map(|w| w - level.digits), out);
+        self.convert(&r, k.saturating_sub(1), Some(level.digits), out);
+    }
+}
+
+// a, normalized, in the given radix with lower case letters.
+pub fn to_str_radix<L: Appendage>(a: &[L], radix: u32) -> String {
+    assert!((2..=36).contains(&radix));
+    if a.len() < DC_THRESHOLD {
+        return basecase(a, radix);
+    }
+    let powers = Powers::new(a, radix);
+    let mut s = String::new();
+    powers.convert(a, powers.levels.len() - 1, None, &mut s);
+    return s;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check<L: Appendage>() {
+        // 3^k has digits all over the place in every radix
+        let mut a: Vec<L> = vec![L::ONE];
+        let mut decimal = vec![1u8];
+        for i in 0..4000 {
+            limbs::mul_small_add(&mut a, L::from_u64(3), L::ZERO);
+            let mut carry = 0;
+            for d in decimal.iter_mut() {
+                let t = *d * 3 + carry;
+                *d = t % 10;
+                carry = t / 10;
+            }
+            if carry > 0 {
+                decimal.push(carry);
+            }
+            if i % 397 == 0 || i == 3999 {
+                let expect: String = decimal.iter().rev().map(|&d| (b'0' + d) as char).collect();
+                assert_eq!(to_str_radix(&a, 10), expect);
+                assert_eq!(to_str_radix(&a, 10), basecase(&a, 10));
+                for &radix in &[2, 7, 16, 36] {
+                    assert_eq!(to_str_radix(&a, radix), basecase(&a, radix));
+                }
+            }
+        }
+        // powers of the radix are where the padding goes wrong
+        let p = limbs::shl(&[L::ONE], 4000);
+        let s = to_str_radix(&p, 16);
+        assert_eq!(s.len(), 1001);
+        assert!(s.starts_with('1') && s[1..].chars().all(|c| c == '0'));
+    }
+
+    #[test]
+    fn matches_basecase() {
+        check::<u64>();
+        check::<u32>();
+    }
+}
@@ -3,8 +3,12 @@
 use super::parse::{*};
 use super::sign::{*};
 use super::natural::{*};
+use super::checked::{*};
+
+use std::convert::TryFrom;
 
 use rug::Integer as RugInteger;
+use rug::integer::Order;
 
 use_arith!();
 
@@ -79,6 +83,113 @@ size_plain_op!(Add, add);
 size_assign_op!(AddAssign, add_assign);
 size_plain_op!(Sub, sub);
 size_assign_op!(SubAssign, sub_assign);
+size_plain_op!(Mul, mul);
+size_assign_op!(MulAssign, mul_assign);
+size_plain_op!(Div, div);
+size_plain_op!(Rem, rem);
+size_plain_op!(BitAnd, bitand);
+
+shift_ops!(RugInteger, u32);
+
+// rug is signed, so just do it and look at the sign.
+macro_rules! rug_try_arith {
+    ($U:ty, $x:ident, $conv:expr) => {
+        impl TryAdd<$U> for RugInteger {
+            fn try_add(&self, other: $U) -> Option<Self> {
+                let $x = other;
+                let v = RugInteger::from(self + $conv);
+                match v.is_negative() {
+                    true => None,
+                    false => Some(v),
+                }
+            }
+        }
+
+        impl TrySub<$U> for RugInteger {
+            fn try_sub(&self, other: $U) -> Option<Self> {
+                let $x = other;
+                let v = RugInteger::from(self - $conv);
+                match v.is_negative() {
+                    true => None,
+                    false => Some(v),
+                }
+            }
+        }
+    }
+}
+
+rug_try_arith!(u8, x, x);
+rug_try_arith!(u16, x, x);
+rug_try_arith!(u32, x, x);
+rug_try_arith!(u64, x, x);
+rug_try_arith!(u128, x, x);
+rug_try_arith!(usize, x, x as u64);
+rug_try_arith!(i8, x, x);
+rug_try_arith!(i16, x, x);
+rug_try_arith!(i32, x, x);
+rug_try_arith!(i64, x, x);
+rug_try_arith!(i128, x, x);
+rug_try_arith!(isize, x, x as i64);
+rug_try_arith!(RugInteger, x, x);
+
+checked_with_all_primitives!(RugInteger);
+checked_with!(RugInteger, RugInteger);
+checked_with_self!(RugInteger);
+
+impl Natural for WrappedNatural<RugInteger> {
+    fn div_rem(self, other: Self) -> (Self, Self) {
+        let (q, r) = (self.0).div_rem(other.0);
+        return (Self(q), Self(r));
+    }
+
+    // rug counts bits with a u32, anything past that is a high zero.
+    fn bit(&self, i: usize) -> bool {
+        match u32::try_from(i) {
+            Ok(i) => (self.0).get_bit(i),
+            Err(_) => false,
+        }
+    }
+
+    fn set_bit(&mut self, i: usize, value: bool) {
+        let i = u32::try_from(i).expect("bit index too large");
+        (self.0).set_bit(i, value);
+    }
 
-impl Natural for WrappedNatural<RugInteger> {}
+    fn significant_bits(&self) -> usize {
+        (self.0).significant_bits() as usize
+    }
+
+    fn trailing_zeros(&self) -> Option<usize> {
+        (self.0).find_one(0).map(|i| i as usize)
+    }
+
+    fn to_str_radix(&self, radix: u32) -> String {
+        (self.0).to_string_radix(radix as i32)
+    }
+
+    // mpz_import and mpz_export underneath.
+    fn from_bytes_le(bytes: &[u8]) -> Self {
+        WrappedNatural(RugInteger::from_digits(bytes, Order::Lsf))
+    }
+
+    fn from_bytes_be(bytes: &[u8]) -> Self {
+        WrappedNatural(RugInteger::from_digits(bytes, Order::Msf))
+    }
+
+    fn to_bytes_le(&self) -> Vec<u8> {
+        (self.0).to_digits(Order::Lsf)
+    }
+
+    fn to_bytes_be(&self) -> Vec<u8> {
+        (self.0).to_digits(Order::Msf)
+    }
+
+    fn from_u64_limbs(limbs: &[u64]) -> Self {
+        WrappedNatural(RugInteger::from_digits(limbs, Order::Lsf))
+    }
+
+    fn to_u64_limbs(&self) -> Vec<u64> {
+        (self.0).to_digits(Order::Lsf)
+    }
+}
 
@@ -0,0 +1,35 @@
+#![warn(rust_2018_idioms)]
+
+// What the tests share: inputs from a seeded generator, so a failure can
+// be replayed, and the generic checks run on every backend.
+
+// xorshift64, which any nonzero seed starts.
+pub(crate) fn next(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    return *state;
+}
+
+// A #[test] for each generic check f::<T>() named and each backend, in a
+// module for the backend, so a failure reads like native32::bits.
+macro_rules! backend_tests {
+    ($($f:ident),* $(,)?) => {
+        backend_tests!(@on native, $crate::big::Native; $($f),*);
+        backend_tests!(@on native32, $crate::big::Native32; $($f),*);
+        #[cfg(feature="rug_integer")]
+        backend_tests!(@on rug_integer, $crate::big::Rug; $($f),*);
+    };
+    (@on $backend:ident, $T:ty; $($f:ident),*) => {
+        mod $backend {
+            $(
+                #[test]
+                fn $f() {
+                    super::$f::<$T>();
+                }
+            )*
+        }
+    };
+}
+
+pub(crate) use backend_tests;
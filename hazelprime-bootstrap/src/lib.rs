@@ -5,6 +5,10 @@
 
 include!(concat!(env!("OUT_DIR"), "/crate_top.rs"));
 
-mod big;
+pub mod types;
+pub mod big;
+pub mod residue;
+pub mod primality;
+pub mod trial;
 // mod extent;
 
@@ -0,0 +1,123 @@
+#![warn(rust_2018_idioms)]
+
+// Primality tests written once against Natural, so they run on whichever
+// backend is plugged in. The exponentiations go through Residue, which
+// picks the reduction from the form of N.
+//
+// Only k*2^e+1 is handled for now. k*2^e-1 would want LLR, but nothing
+// parses those yet.
+
+use crate::big::Natural;
+use crate::residue::{Residue, Modulus};
+
+// The odd primes tried as Proth bases, a non-residue turns up among
+// these for anything that isn't a square.
+const SMALL_PRIMES: [u32; 24] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41,
+    43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+];
+
+fn pow_mod(b: u64, mut e: u64, m: u64) -> u64 {
+    let mut r = 1 % m;
+    let mut b = b % m;
+    while e > 0 {
+        if e & 1 == 1 {
+            r = r * b % m;
+        }
+        b = b * b % m;
+        e >>= 1;
+    }
+    return r;
+}
+
+// k*2^e+1 mod p.
+fn proth_mod(k: u32, e: usize, p: u32) -> u64 {
+    let p = p as u64;
+    return ((k as u64 % p) * pow_mod(2, e as u64, p) + 1) % p;
+}
+
+// Finds a base a with (a/N) = -1. For N = 1 mod 4 reciprocity makes that
+// (N mod a / a), which Euler's criterion gives us with small numbers.
+// Also returns a if it divides N instead.
+pub fn proth_base(k: u32, e: usize) -> Option<u32> {
+    assert!(e >= 2);
+    for &a in SMALL_PRIMES.iter() {
+        let r = proth_mod(k, e, a);
+        if r == 0 || pow_mod(r, (a as u64 - 1) / 2, a as u64) == a as u64 - 1 {
+            return Some(a);
+        }
+    }
+    return None;
+}
+
+// Proth's theorem: N = k*2^e+1 with k < 2^e is prime if and only if
+// a^((N-1)/2) = -1 mod N, for a quadratic non-residue a. Returns the
+// verdict, the base and a^((N-1)/2) mod N.
+pub fn proth<T: Natural>(k: u32, e: usize) -> (bool, u32, T) {
+    assert!(k > 0);
+    assert!(e >= 2 && (e >= 32 || (k as u64) < (1u64 << e)), "Not a Proth number");
+    let a = proth_base(k, e).expect("No small quadratic non-residue, N is probably a square");
+    let n = (T::from(k) << e) + 1u8;
+    if proth_mod(k, e, a) == 0 {
+        return (n == T::from(a), a, T::from(0u8));
+    }
+    let m = Modulus::new(n.clone());
+    let r = Residue::new(T::from(a), &m).pow(&((n - 1u8) >> 1));
+    let prime = r == -Residue::one(&m);
+    return (prime, a, r.into_natural());
+}
+
+// Fermat probable prime test, base^(N-1) = 1 mod N. Returns the verdict
+// and base^(N-1) mod N.
+pub fn fermat_prp<T: Natural>(n: &T, base: u32) -> (bool, T) {
+    assert!(*n > T::from(1u8));
+    let m = Modulus::new(n.clone());
+    let r = Residue::new(T::from(base), &m).pow(&(n.clone() - 1u8));
+    let prp = r == Residue::one(&m);
+    return (prp, r.into_natural());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::big::testing::backend_tests;
+
+    backend_tests!(proth_primes, proth_composites, proth_residue, prp);
+
+    #[test]
+    fn base() {
+        assert_eq!(proth_base(1, 2), Some(3));
+        assert_eq!(proth_base(3, 5), Some(5));
+        // 13 = 3*2^2+1 is 1 mod 3, so 3 doesn't work.
+        assert_eq!(proth_base(3, 2), Some(5));
+    }
+
+    fn proth_primes<T: Natural + std::fmt::Debug>() {
+        let primes = [(1, 2), (3, 2), (1, 4), (3, 5), (1, 16), (5, 7), (3, 189), (5, 1947)];
+        for &(k, e) in primes.iter() {
+            assert!(proth::<T>(k, e).0, "{}*2^{}+1 is prime", k, e);
+        }
+    }
+
+    fn proth_composites<T: Natural + std::fmt::Debug>() {
+        let composites = [(1, 5), (7, 3), (5, 8), (3, 190), (5, 1946)];
+        for &(k, e) in composites.iter() {
+            assert!(!proth::<T>(k, e).0, "{}*2^{}+1 is composite", k, e);
+        }
+    }
+
+    fn proth_residue<T: Natural + std::fmt::Debug>() {
+        let (prime, a, r) = proth::<T>(3, 5);
+        assert!(prime);
+        assert_eq!(a, 5);
+        assert_eq!(r, T::from(96u8));
+    }
+
+    fn prp<T: Natural + std::fmt::Debug>() {
+        assert!(fermat_prp(&T::from(u128::MAX >> 1), 3).0);
+        assert!(fermat_prp(&T::from(1000003u32), 2).0);
+        assert!(!fermat_prp(&T::from(1000001u32), 2).0);
+        // A Carmichael number fools it for coprime bases.
+        assert!(fermat_prp(&T::from(561u32), 2).0);
+    }
+}
@@ -1,7 +1,227 @@
-use rug::Integer;
-use rug::Assign;
+#![warn(rust_2018_idioms)]
 
-pub struct Residue {
-    
+use std::rc::Rc;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::ops::Add;
+use std::ops::Sub;
+use std::ops::Mul;
+use std::ops::Neg;
+
+use crate::big::Natural;
+
+mod modulus;
+
+pub use modulus::Modulus;
+
+// An element of Z/NZ, always kept in [0, N).
+#[derive(Clone, Debug)]
+pub struct Residue<T> {
+    v: T,
+    modulus: Rc<Modulus<T>>,
+}
+
+impl<T: Natural> Residue<T> {
+    pub fn new(v: T, modulus: &Rc<Modulus<T>>) -> Self {
+        return Residue {
+            v: modulus.reduce(v),
+            modulus: Rc::clone(modulus),
+        };
+    }
+
+    pub fn zero(modulus: &Rc<Modulus<T>>) -> Self {
+        return Self::new(T::from(0u8), modulus);
+    }
+
+    pub fn one(modulus: &Rc<Modulus<T>>) -> Self {
+        return Self::new(T::from(1u8), modulus);
+    }
+
+    pub fn modulus(&self) -> &Rc<Modulus<T>> {
+        &self.modulus
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.v == T::from(0u8)
+    }
+
+    // The representative in [0, N).
+    pub fn to_natural(&self) -> T {
+        self.v.clone()
+    }
+
+    pub fn into_natural(self) -> T {
+        self.v
+    }
+
+    pub fn square(&self) -> Self {
+        return self.clone() * self.clone();
+    }
+
+    pub fn pow(&self, e: &T) -> Self {
+        return Residue {
+            v: self.modulus.pow(&self.v, e),
+            modulus: Rc::clone(&self.modulus),
+        };
+    }
+
+    // Extended Euclid, keeping the coefficient of self reduced mod N so
+    // that it never goes negative. None if self and N aren't coprime.
+    pub fn inverse(&self) -> Option<Self> {
+        let zero = T::from(0u8);
+        let n = self.modulus.n().clone();
+        let mut r0 = n.clone();
+        let mut r1 = self.v.clone();
+        let mut s0 = zero.clone();
+        let mut s1 = T::from(1u8);
+        while r1 != zero {
+            let (q, r2) = r0.div_rem(r1.clone());
+            let qs = (q * s1.clone()) % n.clone();
+            let s2 = if s0 >= qs {
+                s0 - qs
+            } else {
+                s0 + n.clone() - qs
+            };
+            r0 = r1;
+            r1 = r2;
+            s0 = s1;
+            s1 = s2;
+        }
+        if r0 != T::from(1u8) {
+            return None;
+        }
+        return Some(Self::new(s0, &self.modulus));
+    }
+
+    fn assert_same_modulus(&self, other: &Self) {
+        assert!(
+            Rc::ptr_eq(&self.modulus, &other.modulus)
+            || self.modulus == other.modulus,
+            "Residues have different moduli"
+        );
+    }
+}
+
+impl<T: Natural> PartialEq for Residue<T> {
+    fn eq(&self, other: &Self) -> bool {
+        (Rc::ptr_eq(&self.modulus, &other.modulus) || self.modulus == other.modulus)
+            && self.v == other.v
+    }
+}
+
+impl<T: Natural> Eq for Residue<T> {}
+
+impl<T: Natural> Display for Residue<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        self.v.fmt(f)
+    }
+}
+
+impl<T: Natural> Add for Residue<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        self.assert_same_modulus(&other);
+        let mut v = self.v + other.v;
+        if v >= *self.modulus.n() {
+            v -= self.modulus.n().clone();
+        }
+        return Residue {v, modulus: self.modulus};
+    }
+}
+
+impl<T: Natural> Sub for Residue<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self.assert_same_modulus(&other);
+        let v = if self.v >= other.v {
+            self.v - other.v
+        } else {
+            self.v + self.modulus.n().clone() - other.v
+        };
+        return Residue {v, modulus: self.modulus};
+    }
+}
+
+impl<T: Natural> Mul for Residue<T> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        self.assert_same_modulus(&other);
+        let v = self.modulus.reduce(self.v * other.v);
+        return Residue {v, modulus: self.modulus};
+    }
 }
 
+impl<T: Natural> Neg for Residue<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        if self.is_zero() {
+            return self;
+        }
+        let v = self.modulus.n().clone() - self.v;
+        return Residue {v, modulus: self.modulus};
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::big::{WrappedNatural, NativeNatural};
+    use crate::big::testing::backend_tests;
+
+    backend_tests!(ops, pow, inverse, large_prime);
+
+    fn ops<T: Natural + std::fmt::Debug>() {
+        let m = Modulus::new(T::from(101u8));
+        let r = |x: u32| Residue::new(T::from(x), &m);
+        assert_eq!(r(205).to_string(), "3");
+        assert_eq!(r(3) + r(99), r(1));
+        assert_eq!(r(3) - r(5), r(99));
+        assert_eq!(-r(5), r(96));
+        assert_eq!(-r(0), r(0));
+        assert_eq!(r(50) * r(3), r(49));
+        assert_eq!(r(10).square(), r(100));
+        assert_eq!(r(7).into_natural(), T::from(7u8));
+        let m2 = Modulus::new(T::from(100u8));
+        assert!(Residue::new(T::from(1u8), &m2) != r(1));
+    }
+
+    fn pow<T: Natural + std::fmt::Debug>() {
+        let m = Modulus::new(T::from(101u8));
+        let r = |x: u32| Residue::new(T::from(x), &m);
+        assert_eq!(r(3).pow(&T::from(100u8)), r(1));
+        assert_eq!(r(3).pow(&T::from(0u8)), r(1));
+    }
+
+    fn inverse<T: Natural + std::fmt::Debug>() {
+        let m = Modulus::new(T::from(101u8));
+        let r = |x: u32| Residue::new(T::from(x), &m);
+        assert_eq!(r(3).inverse().unwrap(), r(34));
+        assert_eq!(r(0).inverse(), None);
+        let m2 = Modulus::new(T::from(100u8));
+        assert_eq!(Residue::new(T::from(10u8), &m2).inverse(), None);
+        assert_eq!(Residue::new(T::from(3u8), &m2).inverse().unwrap().to_natural(), T::from(67u8));
+    }
+
+    // 2^127 - 1 is prime, so Fermat's little theorem holds for it.
+    fn large_prime<T: Natural + std::fmt::Debug>() {
+        let p = T::from(u128::MAX >> 1);
+        let mp = Modulus::new(p.clone());
+        let three = Residue::new(T::from(3u8), &mp);
+        assert_eq!(three.pow(&(p - 1u8)), Residue::one(&mp));
+        assert_eq!(three.inverse().unwrap() * three, Residue::one(&mp));
+    }
+
+    #[test]
+    #[should_panic]
+    fn different_moduli() {
+        type N = WrappedNatural<NativeNatural>;
+        let a = Residue::new(N::from(1u8), &Modulus::new(N::from(7u8)));
+        let b = Residue::new(N::from(1u8), &Modulus::new(N::from(11u8)));
+        let _ = a + b;
+    }
+}
@@ -0,0 +1,327 @@
+#![warn(rust_2018_idioms)]
+
+use std::rc::Rc;
+
+use crate::big::Natural;
+
+// The N that residues are taken modulo, along with whatever was worth
+// working out ahead of time to reduce by it quickly. Residues hold an Rc
+// to one of these so that a whole computation shares a single copy.
+#[derive(Debug)]
+pub enum Modulus<T> {
+    // Anything else. Barrett for products, Montgomery for powers when N
+    // is odd.
+    Generic {
+        n: T,
+        barrett: Barrett<T>,
+        montgomery: Option<Montgomery<T>>,
+    },
+    // k*2^shift + 1, with k < 2^shift.
+    Proth {
+        n: T,
+        k: T,
+        shift: usize,
+        mask: T,
+    },
+    // 2^shift - 1. N is its own mask.
+    Mersenne {
+        n: T,
+        shift: usize,
+    },
+    // 2^shift + 1.
+    Fermat {
+        n: T,
+        shift: usize,
+        mask: T,
+    },
+}
+
+use Modulus::{*};
+
+// m = floor(4^k / N) where N has k bits. Good for x < limit = 4^k.
+#[derive(Debug)]
+pub struct Barrett<T> {
+    k: usize,
+    m: T,
+    limit: T,
+}
+
+// R = 2^k > N, n_prime = -N^-1 mod R and r2 = R^2 mod N.
+#[derive(Debug)]
+pub struct Montgomery<T> {
+    k: usize,
+    mask: T,
+    n_prime: T,
+    r2: T,
+}
+
+impl<T: Natural> Modulus<T> {
+    // Picks the cheapest reduction N's form allows.
+    pub fn new(n: T) -> Rc<Self> {
+        let zero = T::from(0u8);
+        let one = T::from(1u8);
+        assert!(n != zero, "Modulus can't be zero");
+        if n > T::from(2u8) {
+            if (n.clone() + 1u8) & n.clone() == zero {
+                let shift = n.significant_bits();
+                return Rc::new(Mersenne {n, shift});
+            }
+            let n_minus_one = n.clone() - 1u8;
+            let shift = n_minus_one.trailing_zeros().unwrap();
+            let k = n_minus_one >> shift;
+            if k == one {
+                return Self::fermat(shift);
+            }
+            if shift > 0 && k.significant_bits() <= shift {
+                return Self::proth(k, shift);
+            }
+        }
+        return Self::generic(n);
+    }
+
+    // Always uses the generic reductions, whatever N looks like.
+    pub fn generic(n: T) -> Rc<Self> {
+        assert!(n != T::from(0u8), "Modulus can't be zero");
+        let barrett = Barrett::new(&n);
+        let montgomery = if n.clone() & 1u8 == T::from(1u8) {
+            Some(Montgomery::new(&n))
+        } else {
+            None
+        };
+        return Rc::new(Generic {n, barrett, montgomery});
+    }
+
+    pub fn proth(k: T, shift: usize) -> Rc<Self> {
+        assert!(k != T::from(0u8));
+        let mask = (T::from(1u8) << shift) - 1u8;
+        let n = (k.clone() << shift) + 1u8;
+        return Rc::new(Proth {n, k, shift, mask});
+    }
+
+    pub fn mersenne(shift: usize) -> Rc<Self> {
+        assert!(shift > 0);
+        let n = (T::from(1u8) << shift) - 1u8;
+        return Rc::new(Mersenne {n, shift});
+    }
+
+    pub fn fermat(shift: usize) -> Rc<Self> {
+        let mask = (T::from(1u8) << shift) - 1u8;
+        let n = mask.clone() + 2u8;
+        return Rc::new(Fermat {n, shift, mask});
+    }
+
+    pub fn n(&self) -> &T {
+        match self {
+            Generic {n, ..} => n,
+            Proth {n, ..} => n,
+            Mersenne {n, ..} => n,
+            Fermat {n, ..} => n,
+        }
+    }
+
+    // x mod N for any x.
+    pub(super) fn reduce(&self, x: T) -> T {
+        let n = self.n();
+        if x < *n {
+            return x;
+        }
+        match self {
+            Generic {n, barrett, ..} => {
+                if x < barrett.limit {
+                    return barrett.reduce(x, n);
+                }
+                return x % n.clone();
+            },
+            Proth {n, k, shift, mask} => {
+                // k*2^shift = -1, so hi*2^shift + lo = r*2^shift + lo - q
+                // where hi = q*k + r.
+                let lo = x.clone() & mask.clone();
+                let (q, r) = (x >> *shift).div_rem(k.clone());
+                let q = self.reduce(q);
+                let a = (r << *shift) + lo;
+                if a >= q {
+                    return a - q;
+                }
+                return a + n.clone() - q;
+            },
+            Mersenne {n, shift} => {
+                // 2^shift = 1, fold the top onto the bottom.
+                let mut x = x;
+                while x > *n {
+                    x = (x.clone() >> *shift) + (x & n.clone());
+                }
+                if x == *n {
+                    return T::from(0u8);
+                }
+                return x;
+            },
+            Fermat {n, shift, mask} => {
+                // 2^shift = -1, subtract the top from the bottom.
+                let lo = x.clone() & mask.clone();
+                let hi = self.reduce(x >> *shift);
+                if lo >= hi {
+                    return lo - hi;
+                }
+                return lo + n.clone() - hi;
+            },
+        }
+    }
+
+    // a^e mod N for a < N, left to right.
+    pub(super) fn pow(&self, a: &T, e: &T) -> T {
+        if let Generic {n, montgomery: Some(mont), ..} = self {
+            return mont.pow(a, e, n);
+        }
+        let mut r = self.reduce(T::from(1u8));
+        for bit in e.bits().rev() {
+            r = self.reduce(r.clone() * r);
+            if bit {
+                r = self.reduce(r * a.clone());
+            }
+        }
+        return r;
+    }
+}
+
+// Only N matters, however the reduction was set up.
+impl<T: Natural> PartialEq for Modulus<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.n() == other.n()
+    }
+}
+
+impl<T: Natural> Eq for Modulus<T> {}
+
+impl<T: Natural> Barrett<T> {
+    fn new(n: &T) -> Self {
+        let k = n.significant_bits();
+        let limit = T::from(1u8) << (2 * k);
+        let m = limit.clone() / n.clone();
+        return Barrett {k, m, limit};
+    }
+
+    // x mod n for x < 4^k. The estimate is at most two short.
+    fn reduce(&self, x: T, n: &T) -> T {
+        let q = ((x.clone() >> (self.k - 1)) * self.m.clone()) >> (self.k + 1);
+        let mut r = x - q * n.clone();
+        while r >= *n {
+            r -= n.clone();
+        }
+        return r;
+    }
+}
+
+impl<T: Natural> Montgomery<T> {
+    fn new(n: &T) -> Self {
+        let k = n.significant_bits();
+        let r = T::from(1u8) << k;
+        let mask = r.clone() - 1u8;
+        // Newton's iteration for N^-1 mod R, each step doubles the number
+        // of correct bits.
+        let one = T::from(1u8);
+        let mut inv = one.clone();
+        while (n.clone() * inv.clone()) & mask.clone() != one {
+            let t = (n.clone() * inv.clone()) & mask.clone();
+            inv = (inv * (r.clone() + 2u8 - t)) & mask.clone();
+        }
+        let n_prime = (r.clone() - inv) & mask.clone();
+        let r2 = (r.clone() * r) % n.clone();
+        return Montgomery {k, mask, n_prime, r2};
+    }
+
+    // t * R^-1 mod n for t < n*R.
+    fn redc(&self, t: T, n: &T) -> T {
+        let m = ((t.clone() & self.mask.clone()) * self.n_prime.clone()) & self.mask.clone();
+        let u = (t + m * n.clone()) >> self.k;
+        if u >= *n {
+            return u - n.clone();
+        }
+        return u;
+    }
+
+    // a^e mod n, converting a in and the result out of Montgomery form.
+    fn pow(&self, a: &T, e: &T, n: &T) -> T {
+        let mut r = self.redc(self.r2.clone(), n);
+        let a = self.redc(a.clone() * self.r2.clone(), n);
+        for bit in e.bits().rev() {
+            r = self.redc(r.clone() * r, n);
+            if bit {
+                r = self.redc(r * a.clone(), n);
+            }
+        }
+        return self.redc(r, n);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::big::testing::backend_tests;
+
+    backend_tests!(proth, mersenne, fermat, generic);
+
+    fn check<T: Natural + std::fmt::Debug>(m: &Modulus<T>) {
+        let n = m.n().clone();
+        let big = T::from_str("123456789012345678901234567890123456789012345678901234567890").ok().unwrap();
+        let xs = vec![
+            T::from(0u8),
+            n.clone() - 1u8,
+            n.clone(),
+            n.clone() + 1u8,
+            (n.clone() - 1u8) * (n.clone() - 1u8),
+            n.clone() * n.clone() * 7u8 + 5u8,
+            big.clone(),
+            big.clone() * big,
+        ];
+        for x in xs {
+            assert_eq!(m.reduce(x.clone()), x.clone() % n.clone(), "{:?} mod {:?}", x, n);
+        }
+        let a = T::from(3u8) % n.clone();
+        let e = n.clone() + 12345u32;
+        let mut expect = T::from(1u8) % n.clone();
+        let mut i = T::from(0u8);
+        while i < T::from(200u8) {
+            expect = (expect * a.clone()) % n.clone();
+            i += 1u8;
+        }
+        assert_eq!(m.pow(&a, &T::from(200u8)), expect);
+        let generic = Modulus::generic(n.clone());
+        assert_eq!(m.pow(&a, &e), generic.pow(&a, &e));
+    }
+
+    fn proth<T: Natural + std::fmt::Debug>() {
+        let p = Modulus::<T>::new(T::from(641u32));
+        assert!(matches!(*p, Proth {shift: 7, ..}));
+        check(&p);
+        check(&Modulus::<T>::proth(T::from(5u8), 200));
+        check(&Modulus::<T>::proth(T::from(1u8), 64));
+    }
+
+    fn mersenne<T: Natural + std::fmt::Debug>() {
+        let m = Modulus::<T>::new(T::from(u128::MAX >> 1));
+        assert!(matches!(*m, Mersenne {shift: 127, ..}));
+        check(&m);
+        check(&Modulus::<T>::mersenne(61));
+        check(&Modulus::<T>::mersenne(3));
+    }
+
+    fn fermat<T: Natural + std::fmt::Debug>() {
+        let f = Modulus::<T>::new(T::from(65537u32));
+        assert!(matches!(*f, Fermat {shift: 16, ..}));
+        check(&f);
+        check(&Modulus::<T>::fermat(128));
+        assert_eq!(*Modulus::<T>::generic(T::from(65537u32)), *f);
+    }
+
+    fn generic<T: Natural + std::fmt::Debug>() {
+        let g = Modulus::<T>::new(T::from(1000003u32));
+        assert!(matches!(*g, Generic {montgomery: Some(_), ..}));
+        check(&g);
+        let g = Modulus::<T>::new(T::from(1u8) << 100);
+        assert!(matches!(*g, Generic {montgomery: None, ..}));
+        check(&g);
+        check(&Modulus::<T>::generic(T::from(1u8)));
+        check(&Modulus::<T>::generic(T::from(2u8)));
+        check(&Modulus::<T>::generic(T::from_str("340282366920938463463374607431768211507").ok().unwrap()));
+    }
+}
@@ -0,0 +1,78 @@
+#![warn(rust_2018_idioms)]
+
+// Trial division of k*2^e+1, to throw out candidates before spending a
+// full Proth test on them. Candidate factors are fixed-size naturals so
+// the inner loop never allocates.
+
+use crate::big::{FixedNatural, Fixed64, Montgomery};
+
+// The primes below limit, by the sieve of Eratosthenes over odd numbers.
+pub fn primes_below(limit: u64) -> Vec<u64> {
+    if limit <= 2 {
+        return Vec::new();
+    }
+    // composite[i] is for 2i+1
+    let mut composite = vec![false; (limit / 2) as usize];
+    let mut primes = vec![2];
+    for i in 1..composite.len() {
+        if composite[i] {
+            continue;
+        }
+        let p = 2 * i as u64 + 1;
+        primes.push(p);
+        let mut j = p * p / 2;
+        while j < composite.len() as u64 {
+            composite[j as usize] = true;
+            j += p;
+        }
+    }
+    return primes;
+}
+
+// p divides k*2^e+1 exactly when k*2^e = -1 mod p. Odd p get one
+// Montgomery set up for both products.
+pub fn proth_divisible<const LIMBS: usize>(k: u32, e: u64, p: &FixedNatural<LIMBS>) -> bool {
+    let (two, e, k) = (FixedNatural::<LIMBS>::from(2u8), FixedNatural::from(e), FixedNatural::from(k));
+    let t = match p.is_odd() {
+        true => {
+            let m = Montgomery::new(p);
+            m.mul_mod(&m.pow_mod(&two, &e), &k)
+        }
+        false => two.pow_mod(&e, p).mul_mod(&k, p),
+    };
+    return t + 1u8 == *p;
+}
+
+// The smallest prime below limit dividing k*2^e+1, which may be N itself
+// if N is that small.
+pub fn proth_factor(k: u32, e: u64, limit: u64) -> Option<u64> {
+    primes_below(limit).into_iter().find(|&p| proth_divisible(k, e, &Fixed64::from(p)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::big::Fixed128;
+
+    #[test]
+    fn sieve() {
+        assert_eq!(primes_below(2), Vec::<u64>::new());
+        assert_eq!(primes_below(3), vec![2]);
+        assert_eq!(primes_below(30), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert_eq!(primes_below(1_000_000).len(), 78498);
+    }
+
+    #[test]
+    fn factors() {
+        // Euler's factor of F5
+        assert_eq!(proth_factor(1, 32, 1000), Some(641));
+        assert_eq!(proth_factor(5, 26606, 100), Some(3));
+        assert_eq!(proth_factor(5, 26607, 100_000), None);
+        assert_eq!(proth_factor(3, 2, 100), Some(13));
+        assert_eq!(proth_factor(3, 0, 100), Some(2));
+        // F7 = 59649589127497217 * 5704689200685129054721
+        assert!(proth_divisible(1, 128, &Fixed64::from(59649589127497217u64)));
+        assert!(proth_divisible(1, 128, &Fixed128::from(5704689200685129054721u128)));
+        assert!(!proth_divisible(1, 128, &Fixed128::from(5704689200685129054723u128)));
+    }
+}
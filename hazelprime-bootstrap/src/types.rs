@@ -1,7 +1,127 @@
 #![warn(rust_2018_idioms)]
 
-pub trait Appendage {
-    const BITS: usize;
-    
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::BitAnd;
+use std::ops::BitOr;
+use std::ops::Not;
+use std::ops::Shl;
+use std::ops::Shr;
+
+// A limb, one machine word of a big number. The limb arithmetic is
+// written against this so it can run on u32 as well as u64.
+pub trait Appendage: Copy + Debug + Default + Eq + Ord + Hash + Send + Sync + 'static
+    + BitAnd<Output = Self> + BitOr<Output = Self> + Not<Output = Self>
+    + Shl<u32, Output = Self> + Shr<u32, Output = Self>
+{
+    const BITS: u32;
+    const ZERO: Self;
+    const ONE: Self;
+    const MAX: Self;
+
+    // The low and high limbs of self * other.
+    fn widening_mul(self, other: Self) -> (Self, Self);
+
+    // self + other + carry, and the carry out.
+    fn add_carry(self, other: Self, carry: bool) -> (Self, bool);
+
+    // self - other - borrow, and the borrow out.
+    fn sub_borrow(self, other: Self, borrow: bool) -> (Self, bool);
+
+    fn leading_zeros(self) -> u32;
+    fn trailing_zeros(self) -> u32;
+
+    // (hi, lo) / d and the remainder. hi < d so that the quotient fits.
+    fn div_wide(hi: Self, lo: Self, d: Self) -> (Self, Self);
+
+    // Truncating conversions.
+    fn from_u64(x: u64) -> Self;
+    fn to_u64(self) -> u64;
+
+    // self * b + c + d, which always fits in two limbs.
+    fn mul_add2(self, b: Self, c: Self, d: Self) -> (Self, Self) {
+        let (lo, hi) = self.widening_mul(b);
+        let (lo, c1) = lo.add_carry(c, false);
+        let (lo, c2) = lo.add_carry(d, false);
+        let (hi, _) = hi.add_carry(Self::ZERO, c1);
+        let (hi, _) = hi.add_carry(Self::ZERO, c2);
+        return (lo, hi);
+    }
+}
+
+macro_rules! appendage {
+    ($T:ty, $W:ty) => {
+        impl Appendage for $T {
+            const BITS: u32 = <$T>::BITS;
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            const MAX: Self = <$T>::MAX;
+
+            fn widening_mul(self, other: Self) -> (Self, Self) {
+                let t = (self as $W) * (other as $W);
+                return (t as $T, (t >> <$T>::BITS) as $T);
+            }
+
+            fn add_carry(self, other: Self, carry: bool) -> (Self, bool) {
+                let (s1, c1) = self.overflowing_add(other);
+                let (s2, c2) = s1.overflowing_add(carry as $T);
+                return (s2, c1 || c2);
+            }
+
+            fn sub_borrow(self, other: Self, borrow: bool) -> (Self, bool) {
+                let (s1, b1) = self.overflowing_sub(other);
+                let (s2, b2) = s1.overflowing_sub(borrow as $T);
+                return (s2, b1 || b2);
+            }
+
+            fn leading_zeros(self) -> u32 {
+                <$T>::leading_zeros(self)
+            }
+
+            fn trailing_zeros(self) -> u32 {
+                <$T>::trailing_zeros(self)
+            }
+
+            fn div_wide(hi: Self, lo: Self, d: Self) -> (Self, Self) {
+                debug_assert!(hi < d);
+                let n = ((hi as $W) << <$T>::BITS) | (lo as $W);
+                return ((n / (d as $W)) as $T, (n % (d as $W)) as $T);
+            }
+
+            fn from_u64(x: u64) -> Self {
+                x as $T
+            }
+
+            fn to_u64(self) -> u64 {
+                self as u64
+            }
+        }
+    }
 }
 
+appendage!(u32, u64);
+appendage!(u64, u128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn u32_ops() {
+        assert_eq!(Appendage::widening_mul(0xffff_ffffu32, 0xffff_ffff), (1, 0xffff_fffe));
+        assert_eq!(u32::MAX.add_carry(0, true), (0, true));
+        assert_eq!(0u32.sub_borrow(0, true), (u32::MAX, true));
+        assert_eq!(<u32 as Appendage>::div_wide(1, 0, 3), (0x5555_5555, 1));
+        assert_eq!(u32::MAX.mul_add2(u32::MAX, u32::MAX, u32::MAX), (u32::MAX, u32::MAX));
+        assert_eq!(Appendage::leading_zeros(1u32), 31);
+        assert_eq!(u32::from_u64(0x1_0000_0002), 2);
+    }
+
+    #[test]
+    fn u64_ops() {
+        assert_eq!(Appendage::widening_mul(u64::MAX, 2), (u64::MAX - 1, 1));
+        assert_eq!(5u64.sub_borrow(7, false), (u64::MAX - 1, true));
+        assert_eq!(<u64 as Appendage>::div_wide(2, 1, 4), (1 << 63, 1));
+        assert_eq!(u64::MAX.mul_add2(u64::MAX, u64::MAX, u64::MAX), (u64::MAX, u64::MAX));
+    }
+}
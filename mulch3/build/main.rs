@@ -0,0 +1,198 @@
+#![warn(rust_2018_idioms)]
+
+// Generates modules.rs: a fully unrolled schoolbook multiply and square
+// for each limb count from 1 to MAX_LIMBS, and the dispatch over them.
+// The kernels scan the product column by column (Comba), so every partial
+// product is a straight line of code with constant indices.
+//
+// MAX_LIMBS is 16 unless MULCH3_MAX_LIMBS says otherwise. Code size grows
+// with its square.
+//
+// It also fingerprints the build for tune.rs, as MULCH3_BUILD_ID, and
+// sets cfg(feature="nightly") on a nightly or dev compiler, the same way
+// hazelprime-bootstrap's build.rs does, for simd.rs.
+
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::Path;
+
+use proc_macro2::TokenStream;
+use quote::{quote, format_ident};
+use version_check as rustc;
+
+type AResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+const DEFAULT_MAX_LIMBS: usize = 16;
+
+fn max_limbs() -> AResult<usize> {
+    println!("cargo:rerun-if-env-changed=MULCH3_MAX_LIMBS");
+    let max = match env::var("MULCH3_MAX_LIMBS") {
+        Ok(s) => s.parse()?,
+        Err(_) => DEFAULT_MAX_LIMBS,
+    };
+    if max < 1 {
+        return Err("MULCH3_MAX_LIMBS must be at least 1".into());
+    }
+    return Ok(max);
+}
+
+// The (i, j) with i + j = k and both below n.
+fn column(n: usize, k: usize) -> std::ops::RangeInclusive<usize> {
+    (k + 1).saturating_sub(n)..=k.min(n - 1)
+}
+
+// x.len() >= n, as !x.is_empty() for n = 1 so the generated code is
+// clippy clean.
+fn at_least(x: &str, n: usize) -> TokenStream {
+    let x = format_ident!("{}", x);
+    if n == 1 {
+        return quote! { !#x.is_empty() };
+    }
+    return quote! { #x.len() >= #n };
+}
+
+fn mul_kernel(n: usize) -> TokenStream {
+    let name = format_ident!("mul_{}", n);
+    let rn = 2 * n;
+    let mut body = Vec::new();
+    for k in 0..rn - 1 {
+        for i in column(n, k) {
+            let j = k - i;
+            body.push(quote! { mac(&mut acc, A::get(a, #i), A::get(b, #j)); });
+        }
+        body.push(quote! { A::set(r, #k, shift(&mut acc)); });
+    }
+    let top = rn - 1;
+    let (a_len, b_len) = (at_least("a", n), at_least("b", n));
+    return quote! {
+        pub fn #name<A: Access>(r: &mut [u64], a: &[u64], b: &[u64]) {
+            assert!(r.len() >= #rn && #a_len && #b_len);
+            let mut acc: Acc = (0, 0, 0);
+            // Every index is a constant below the lengths checked above.
+            unsafe {
+                #(#body)*
+                A::set(r, #top, acc.0);
+            }
+        }
+    };
+}
+
+// Squaring computes each cross product once and adds it twice.
+fn sqr_kernel(n: usize) -> TokenStream {
+    let name = format_ident!("sqr_{}", n);
+    let rn = 2 * n;
+    let mut body = Vec::new();
+    for k in 0..rn - 1 {
+        for i in column(n, k) {
+            let j = k - i;
+            if i < j {
+                body.push(quote! { mac2(&mut acc, A::get(a, #i), A::get(a, #j)); });
+            } else if i == j {
+                body.push(quote! { let x = A::get(a, #i); mac(&mut acc, x, x); });
+            }
+        }
+        body.push(quote! { A::set(r, #k, shift(&mut acc)); });
+    }
+    let top = rn - 1;
+    let a_len = at_least("a", n);
+    return quote! {
+        pub fn #name<A: Access>(r: &mut [u64], a: &[u64]) {
+            assert!(r.len() >= #rn && #a_len);
+            let mut acc: Acc = (0, 0, 0);
+            // Every index is a constant below the lengths checked above.
+            unsafe {
+                #(#body)*
+                A::set(r, #top, acc.0);
+            }
+        }
+    };
+}
+
+fn modules(max: usize) -> TokenStream {
+    let ns: Vec<usize> = (1..=max).collect();
+    let muls: Vec<TokenStream> = ns.iter().map(|&n| mul_kernel(n)).collect();
+    let sqrs: Vec<TokenStream> = ns.iter().map(|&n| sqr_kernel(n)).collect();
+    let mul_names: Vec<_> = ns.iter().map(|n| format_ident!("mul_{}", n)).collect();
+    let sqr_names: Vec<_> = ns.iter().map(|n| format_ident!("sqr_{}", n)).collect();
+    return quote! {
+        pub const MAX_LIMBS: usize = #max;
+
+        pub mod kernels {
+            use crate::access::Access;
+            use crate::comba::{Acc, mac, mac2, shift};
+            #(#muls)*
+            #(#sqrs)*
+        }
+
+        // The kernel for n-limb operands, if there is one.
+        pub fn mul_kernel_with<A: Access>(n: usize) -> Option<MulKernel> {
+            match n {
+                #(#ns => Some(kernels::#mul_names::<A>),)*
+                _ => None,
+            }
+        }
+
+        pub fn sqr_kernel_with<A: Access>(n: usize) -> Option<SqrKernel> {
+            match n {
+                #(#ns => Some(kernels::#sqr_names::<A>),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+// Whether the compiler takes feature flags, which turns on the AVX2 limb
+// helpers.
+fn nightly() -> AResult<bool> {
+    println!("cargo:rustc-check-cfg=cfg(feature, values(\"nightly\"))");
+    let nightly = match rustc::is_feature_flaggable() {
+        Some(nightly) => nightly,
+        None => return Err("Couldn't determine the rustc version.".into()),
+    };
+    if nightly {
+        println!("cargo:rustc-cfg=feature=\"nightly\"");
+    }
+    return Ok(nightly);
+}
+
+// What a thresholds file is good for: the version, the sources, the
+// target, the features, nightly or not and MAX_LIMBS all move the
+// crossovers.
+fn build_id(max: usize, nightly: bool) -> AResult<String> {
+    println!("cargo:rerun-if-changed=src");
+    let mut hasher = DefaultHasher::new();
+    let mut paths = Vec::new();
+    for entry in fs::read_dir("src")? {
+        paths.push(entry?.path());
+    }
+    paths.sort();
+    for path in paths.iter() {
+        path.file_name().hash(&mut hasher);
+        fs::read(path)?.hash(&mut hasher);
+    }
+    env::var("TARGET")?.hash(&mut hasher);
+    let mut features: Vec<String> = env::vars()
+        .map(|(k, _)| k)
+        .filter(|k| k.starts_with("CARGO_FEATURE_"))
+        .collect();
+    features.sort();
+    features.hash(&mut hasher);
+    nightly.hash(&mut hasher);
+    max.hash(&mut hasher);
+    return Ok(format!("{}-{:016x}", env::var("CARGO_PKG_VERSION")?, hasher.finish()));
+}
+
+fn main() -> AResult<()> {
+    println!("cargo:rerun-if-changed=build/main.rs");
+    let out_dir = env::var("OUT_DIR")?;
+    let dest_path = Path::new(&out_dir).join("modules.rs");
+    let mut f = File::create(&dest_path)?;
+    let max = max_limbs()?;
+    writeln!(f, "{}", modules(max))?;
+    let nightly = nightly()?;
+    println!("cargo:rustc-env=MULCH3_BUILD_ID={}", build_id(max, nightly)?);
+    return Ok(());
+}
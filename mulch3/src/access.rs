@@ -0,0 +1,61 @@
+#![warn(rust_2018_idioms)]
+
+// Every limb the kernels read or write goes through here. With the
+// no-bounds-checks feature the public entry points use Unchecked, which
+// skips the index check, otherwise Checked, which is plain indexing. Both
+// are always compiled so the tests can hold one against the other.
+//
+// This is the only place that indexes without checking. The kernels'
+// side of the bargain is to assert their slice lengths on entry and only
+// use indices below them.
+
+pub trait Access {
+    /// a[i].
+    ///
+    /// # Safety
+    ///
+    /// i must be below a.len().
+    unsafe fn get(a: &[u64], i: usize) -> u64;
+
+    /// a[i] = v.
+    ///
+    /// # Safety
+    ///
+    /// i must be below a.len().
+    unsafe fn set(a: &mut [u64], i: usize, v: u64);
+}
+
+pub enum Checked {}
+pub enum Unchecked {}
+
+impl Access for Checked {
+    #[inline(always)]
+    unsafe fn get(a: &[u64], i: usize) -> u64 {
+        a[i]
+    }
+
+    #[inline(always)]
+    unsafe fn set(a: &mut [u64], i: usize, v: u64) {
+        a[i] = v;
+    }
+}
+
+impl Access for Unchecked {
+    #[inline(always)]
+    unsafe fn get(a: &[u64], i: usize) -> u64 {
+        debug_assert!(i < a.len());
+        *a.get_unchecked(i)
+    }
+
+    #[inline(always)]
+    unsafe fn set(a: &mut [u64], i: usize, v: u64) {
+        debug_assert!(i < a.len());
+        *a.get_unchecked_mut(i) = v;
+    }
+}
+
+// What the feature picks for mul, sqr and friends.
+#[cfg(feature="no-bounds-checks")]
+pub type DefaultAccess = Unchecked;
+#[cfg(not(feature="no-bounds-checks"))]
+pub type DefaultAccess = Checked;
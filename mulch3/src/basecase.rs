@@ -0,0 +1,69 @@
+#![warn(rust_2018_idioms)]
+
+// Looped schoolbook multiplication, for the sizes without an unrolled
+// kernel. Same contract as the kernels: lengths are asserted up front,
+// then every index stays below them.
+
+use crate::access::Access;
+use crate::comba::{Acc, mac, add, shift};
+
+// r = a * b into the low a.len() + b.len() limbs of r.
+pub fn mul_basecase<A: Access>(r: &mut [u64], a: &[u64], b: &[u64]) {
+    let (n, m) = (a.len(), b.len());
+    assert!(n > 0 && m > 0 && r.len() >= n + m);
+    unsafe {
+        for k in 0..n + m {
+            A::set(r, k, 0);
+        }
+        for i in 0..n {
+            let x = A::get(a, i);
+            let mut carry = 0;
+            for j in 0..m {
+                let t = (x as u128) * (A::get(b, j) as u128)
+                    + A::get(r, i + j) as u128 + carry as u128;
+                A::set(r, i + j, t as u64);
+                carry = (t >> 64) as u64;
+            }
+            A::set(r, i + m, carry);
+        }
+    }
+}
+
+// r = a * a into the low 2 * a.len() limbs of r, with the cross products
+// summed once and doubled.
+pub fn sqr_basecase<A: Access>(r: &mut [u64], a: &[u64]) {
+    let n = a.len();
+    assert!(n > 0 && r.len() >= 2 * n);
+    unsafe {
+        for k in 0..2 * n {
+            A::set(r, k, 0);
+        }
+        for i in 0..n {
+            let x = A::get(a, i);
+            let mut carry = 0;
+            for j in i + 1..n {
+                let t = (x as u128) * (A::get(a, j) as u128)
+                    + A::get(r, i + j) as u128 + carry as u128;
+                A::set(r, i + j, t as u64);
+                carry = (t >> 64) as u64;
+            }
+            A::set(r, i + n, carry);
+        }
+        // Double, then add the squares down the diagonal.
+        let mut top = 0;
+        for k in 0..2 * n {
+            let v = A::get(r, k);
+            A::set(r, k, (v << 1) | top);
+            top = v >> 63;
+        }
+        let mut acc: Acc = (0, 0, 0);
+        for i in 0..n {
+            let x = A::get(a, i);
+            mac(&mut acc, x, x);
+            add(&mut acc, A::get(r, 2 * i));
+            A::set(r, 2 * i, shift(&mut acc));
+            add(&mut acc, A::get(r, 2 * i + 1));
+            A::set(r, 2 * i + 1, shift(&mut acc));
+        }
+    }
+}
@@ -0,0 +1,44 @@
+#![warn(rust_2018_idioms)]
+
+// The pieces the generated kernels are made of. A column of the product
+// is summed into a three word accumulator, low word first.
+
+pub(crate) type Acc = (u64, u64, u64);
+
+// acc += x * y
+#[inline(always)]
+pub(crate) fn mac(acc: &mut Acc, x: u64, y: u64) {
+    let p = (x as u128) * (y as u128);
+    let (lo, c) = acc.0.overflowing_add(p as u64);
+    // The high word of a product is at most 2^64 - 2, so this can't wrap.
+    let hi = (p >> 64) as u64 + c as u64;
+    let (mid, c) = acc.1.overflowing_add(hi);
+    acc.0 = lo;
+    acc.1 = mid;
+    acc.2 += c as u64;
+}
+
+// acc += 2 * x * y
+#[inline(always)]
+pub(crate) fn mac2(acc: &mut Acc, x: u64, y: u64) {
+    mac(acc, x, y);
+    mac(acc, x, y);
+}
+
+// acc += v
+#[inline(always)]
+pub(crate) fn add(acc: &mut Acc, v: u64) {
+    let (lo, c) = acc.0.overflowing_add(v);
+    let (mid, c) = acc.1.overflowing_add(c as u64);
+    acc.0 = lo;
+    acc.1 = mid;
+    acc.2 += c as u64;
+}
+
+// Takes the finished low word and moves on to the next column.
+#[inline(always)]
+pub(crate) fn shift(acc: &mut Acc) -> u64 {
+    let lo = acc.0;
+    *acc = (acc.1, acc.2, 0);
+    return lo;
+}
@@ -0,0 +1,453 @@
+#![warn(rust_2018_idioms)]
+
+// Everything that multiplies against GMP on the same inputs. First the
+// kernels and the basecase, both checked and unchecked, then the big
+// products and the reductions, property-style: each case is a pair of
+// lengths and operand shapes and a seed for the random limbs, with fixed
+// cases at every threshold and random ones on top.
+//
+// A failing case is printed with its seed. MULCH3_SEED=<seed> runs only
+// the fixed cases with that seed, and the random case made from it, to
+// replay it, and MULCH3_CASES sets how many random cases each test adds,
+// 20 by default.
+
+use std::env;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::*;
+use crate::access::{Access, Checked, Unchecked};
+use crate::testing::{gmp_mul, gmp_sqr, gmp_rem, next};
+
+// All ones has the most carries, the random ones cover the rest.
+fn inputs(n: usize, seed: u64) -> Vec<(Vec<u64>, Vec<u64>)> {
+    let mut state = seed;
+    let mut cases = vec![(vec![u64::MAX; n], vec![u64::MAX; n])];
+    for _ in 0..50 {
+        let a = (0..n).map(|_| next(&mut state)).collect();
+        let b = (0..n).map(|_| next(&mut state)).collect();
+        cases.push((a, b));
+    }
+    return cases;
+}
+
+fn check<A: Access>(n: usize, a: &[u64], b: &[u64], seed: u64) {
+    let (want_mul, want_sqr) = (gmp_mul(a, b), gmp_sqr(a));
+    let mut r = vec![0u64; 2 * n];
+    if let Some(k) = mul_kernel_with::<A>(n) {
+        k(&mut r, a, b);
+        assert_eq!(r, want_mul, "mul_{} seed {:#x}", n, seed);
+    }
+    if let Some(k) = sqr_kernel_with::<A>(n) {
+        k(&mut r, a);
+        assert_eq!(r, want_sqr, "sqr_{} seed {:#x}", n, seed);
+    }
+    mul_basecase::<A>(&mut r, a, b);
+    assert_eq!(r, want_mul, "mul_basecase {} seed {:#x}", n, seed);
+    sqr_basecase::<A>(&mut r, a);
+    assert_eq!(r, want_sqr, "sqr_basecase {} seed {:#x}", n, seed);
+}
+
+#[test]
+fn both_configurations_match_gmp() {
+    for n in 1..=MAX_LIMBS + 8 {
+        let seed = 0x2545_f491_4f6c_dd1d ^ n as u64;
+        for (a, b) in inputs(n, seed).iter() {
+            check::<Checked>(n, a, b, seed);
+            check::<Unchecked>(n, a, b, seed);
+            // and whichever one the feature picked
+            let mut r = vec![0u64; 2 * n];
+            mul(&mut r, a, b);
+            assert_eq!(r, gmp_mul(a, b));
+            sqr(&mut r, a);
+            assert_eq!(r, gmp_sqr(a));
+        }
+    }
+}
+
+#[test]
+fn unequal_lengths() {
+    let mut state = 7;
+    for n in 1..20 {
+        for m in 1..=n {
+            let a: Vec<u64> = (0..n).map(|_| next(&mut state)).collect();
+            let b: Vec<u64> = (0..m).map(|_| next(&mut state)).collect();
+            let mut r = vec![0u64; n + m];
+            mul_basecase::<Checked>(&mut r, &a, &b);
+            assert_eq!(r, gmp_mul(&a, &b));
+            mul_basecase::<Unchecked>(&mut r, &a, &b);
+            assert_eq!(r, gmp_mul(&a, &b));
+        }
+    }
+}
+
+#[test]
+fn dispatch() {
+    assert!(mul_kernel(0).is_none());
+    assert!(mul_kernel(MAX_LIMBS).is_some());
+    assert!(sqr_kernel(MAX_LIMBS + 1).is_none());
+    // r may be longer than 2n, the rest is left alone.
+    let mut r = [7u64; 5];
+    mul(&mut r, &[3, 0], &[5, 1]);
+    assert_eq!(r, [15, 3, 0, 0, 7]);
+}
+
+#[test]
+#[should_panic(expected = "index out of bounds")]
+fn checked_checks() {
+    unsafe {
+        Checked::get(&[1, 2], 2);
+    }
+}
+
+#[test]
+#[should_panic]
+fn kernels_check_lengths() {
+    let mut r = [0u64; 3];
+    kernels::mul_2::<Unchecked>(&mut r, &[1, 2], &[3, 4]);
+}
+
+// Operands that shake out different bugs.
+#[derive(Clone, Copy, Debug)]
+enum Shape {
+    Random,
+    // all ones, the most carries
+    Ones,
+    // one bit set
+    PowerOfTwo,
+    // mostly zero limbs, the rest all ones or random
+    Sparse,
+    // all ones in the top limb and zero below, or for a residue modulo
+    // 2^N+1, 2^N
+    Top,
+}
+
+const SHAPES: [Shape; 5] = [Shape::Random, Shape::Ones, Shape::PowerOfTwo, Shape::Sparse, Shape::Top];
+
+#[derive(Clone, Copy, Debug)]
+struct Case {
+    seed: u64,
+    n: usize,
+    m: usize,
+    a: Shape,
+    b: Shape,
+}
+
+impl Case {
+    // An n and an m limb operand.
+    fn operands(&self) -> (Vec<u64>, Vec<u64>) {
+        let mut state = self.seed | 1;
+        let a = operand(self.n, self.a, &mut state);
+        return (a, operand(self.m, self.b, &mut state));
+    }
+}
+
+fn operand(n: usize, shape: Shape, state: &mut u64) -> Vec<u64> {
+    let mut x = vec![0u64; n];
+    match shape {
+        Shape::Random => {
+            for w in x.iter_mut() {
+                *w = next(state);
+            }
+        }
+        Shape::Ones => {
+            for w in x.iter_mut() {
+                *w = u64::MAX;
+            }
+        }
+        Shape::PowerOfTwo => {
+            let bit = next(state) as usize % (64 * n);
+            x[bit / 64] = 1 << (bit % 64);
+        }
+        Shape::Sparse => {
+            for w in x.iter_mut() {
+                *w = match next(state) % 8 {
+                    0 => u64::MAX,
+                    1 => next(state),
+                    _ => 0,
+                };
+            }
+        }
+        Shape::Top => x[n - 1] = u64::MAX,
+    }
+    return x;
+}
+
+// Each shape against itself and against random limbs.
+fn shape_pairs() -> Vec<(Shape, Shape)> {
+    let mut pairs: Vec<(Shape, Shape)> = SHAPES.iter().map(|&s| (s, s)).collect();
+    pairs.extend(SHAPES[1..].iter().map(|&s| (Shape::Random, s)));
+    return pairs;
+}
+
+// splitmix64, so neighbouring seeds give unrelated cases.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    return x ^ (x >> 31);
+}
+
+// MULCH3_SEED, in hex with a 0x or in decimal.
+fn replay() -> Option<u64> {
+    let s = env::var("MULCH3_SEED").ok()?;
+    let seed = match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    return Some(seed.expect("MULCH3_SEED isn't a number"));
+}
+
+// The cases (n, m, a, b) with seeds from base, or the one being replayed.
+fn fixed(base: u64, cases: &[(usize, usize, Shape, Shape)]) -> Vec<Case> {
+    let replay = replay();
+    let all = cases.iter().enumerate().map(|(i, &(n, m, a, b))| Case {seed: mix(base + i as u64), n, m, a, b});
+    return all.filter(|c| replay.is_none() || replay == Some(c.seed)).collect();
+}
+
+// MULCH3_CASES cases made by case from seeds from base, or the one from
+// the seed being replayed.
+fn random(base: u64, case: impl Fn(u64) -> Case) -> Vec<Case> {
+    if let Some(seed) = replay() {
+        return vec![case(seed)];
+    }
+    let count: u64 = env::var("MULCH3_CASES").map_or(20, |s| s.parse().expect("MULCH3_CASES isn't a number"));
+    return (0..count).map(|i| case(mix(!base + i))).collect();
+}
+
+// Lengths up to about max, mostly small, and shapes from the seed.
+fn random_lengths(seed: u64, max: usize) -> Case {
+    let mut state = seed | 1;
+    let n = 1 + next(&mut state) as usize % (1 + next(&mut state) as usize % max);
+    let m = 1 + next(&mut state) as usize % n;
+    let a = SHAPES[next(&mut state) as usize % SHAPES.len()];
+    let b = SHAPES[next(&mut state) as usize % SHAPES.len()];
+    return Case {seed, n, m, a, b};
+}
+
+// check on every case, naming the one that fails.
+fn run(name: &str, cases: &[Case], mut check: impl FnMut(&Case)) {
+    for case in cases.iter() {
+        if let Err(e) = panic::catch_unwind(AssertUnwindSafe(|| check(case))) {
+            eprintln!("{} failed on {:?}, replay with MULCH3_SEED={:#x}", name, case, case.seed);
+            panic::resume_unwind(e);
+        }
+    }
+}
+
+// Anything that multiplies limbs of any length.
+trait Product {
+    fn mul(&mut self, out: &mut [u64], a: &[u64], b: &[u64]);
+    fn sqr(&mut self, out: &mut [u64], a: &[u64]);
+}
+
+macro_rules! product {
+    ($t:ty) => {
+        impl Product for $t {
+            fn mul(&mut self, out: &mut [u64], a: &[u64], b: &[u64]) {
+                <$t>::mul(self, out, a, b);
+            }
+            fn sqr(&mut self, out: &mut [u64], a: &[u64]) {
+                <$t>::sqr(self, out, a);
+            }
+        }
+    };
+}
+
+product!(Workspace);
+product!(Fft);
+product!(Ntt);
+product!(Ssa);
+
+// mul and sqr, with a fresh Workspace each time.
+struct Functions;
+
+impl Product for Functions {
+    fn mul(&mut self, out: &mut [u64], a: &[u64], b: &[u64]) {
+        mul(out, a, b);
+    }
+    fn sqr(&mut self, out: &mut [u64], a: &[u64]) {
+        sqr(out, a);
+    }
+}
+
+// a * b and a * a into garbage, which has to be written over, with a limb
+// past the end that has to be left alone.
+fn check_products(name: &str, cases: &[Case], p: &mut dyn Product) {
+    run(name, cases, |case| {
+        let (a, b) = case.operands();
+        let mut out = vec![u64::MAX; a.len() + b.len() + 1];
+        out[a.len() + b.len()] = 7;
+        p.mul(&mut out, &a, &b);
+        assert_eq!(out[..a.len() + b.len()], gmp_mul(&a, &b)[..], "{} mul", name);
+        assert_eq!(out[a.len() + b.len()], 7, "{} mul wrote past the product", name);
+        let mut out = vec![u64::MAX; 2 * a.len() + 1];
+        out[2 * a.len()] = 7;
+        p.sqr(&mut out, &a);
+        assert_eq!(out[..2 * a.len()], gmp_sqr(&a)[..], "{} sqr", name);
+        assert_eq!(out[2 * a.len()], 7, "{} sqr wrote past the product", name);
+    });
+}
+
+// Lengths either side of where the kernels stop and of each threshold,
+// and of twice, three and four times the Toom ones, where the pieces
+// cross them.
+fn threshold_lengths(t: &Thresholds) -> Vec<usize> {
+    let mut at = vec![MAX_LIMBS, t.karatsuba, t.toom3, t.toom4, t.fft];
+    for &x in [t.karatsuba, t.toom3, t.toom4].iter() {
+        at.extend((2..=4).map(|j| j * x).filter(|&y| y < t.fft));
+    }
+    let mut lengths: Vec<usize> = at.iter().filter(|&&x| x < 1 << 20).flat_map(|&x| x - 1..=x + 1).collect();
+    lengths.sort_unstable();
+    lengths.dedup();
+    return lengths;
+}
+
+#[test]
+fn workspaces_match_gmp() {
+    let small = Thresholds {karatsuba: 4, toom3: 8, toom4: 12, fft: 40};
+    let configurations = [("small", small), ("default", Thresholds::default()), ("current", Thresholds::current())];
+    for (i, &(name, t)) in configurations.iter().enumerate() {
+        let mut lengths = Vec::new();
+        for &n in threshold_lengths(&t).iter() {
+            for &m in [n, n / 2 + 1, n / 3 + 1].iter() {
+                lengths.extend(shape_pairs().into_iter().map(|(a, b)| (n, m, a, b)));
+            }
+        }
+        let base = 0x3000_0000 * (i as u64 + 1);
+        let mut cases = fixed(base, &lengths);
+        cases.extend(random(base, |seed| random_lengths(seed, t.fft.min(4096) * 2)));
+        check_products(&format!("{} Workspace", name), &cases, &mut Workspace::with_thresholds(t));
+        if name == "current" {
+            check_products("mul and sqr", &cases, &mut Functions);
+        }
+    }
+}
+
+#[test]
+fn transforms_match_gmp() {
+    let mut lengths = Vec::new();
+    for &n in [1, 2, 3, 63, 64, 65, 127, 128, 129, 255, 256, 257, 1023, 1024, 1025, 4096].iter() {
+        for &m in [n, n / 2 + 1, 1].iter() {
+            lengths.extend(shape_pairs().into_iter().map(|(a, b)| (n, m, a, b)));
+        }
+    }
+    let mut cases = fixed(0x7000_0000, &lengths);
+    cases.extend(random(0x7000_0000, |seed| random_lengths(seed, 8192)));
+    check_products("Fft", &cases, &mut Fft::new());
+    check_products("Ntt", &cases, &mut Ntt::new());
+    check_products("Ssa", &cases, &mut Ssa::new());
+    check_products("Ssa threshold 8", &cases, &mut Ssa::with_threshold(8));
+}
+
+// A residue modulo 2^(64 n)+1, n+1 limbs, with Top for 2^(64 n).
+fn ssa_residue(n: usize, shape: Shape, state: &mut u64) -> Vec<u64> {
+    let mut x = match shape {
+        Shape::Top => vec![0u64; n],
+        _ => operand(n, shape, state),
+    };
+    x.push(match shape {
+        Shape::Top => 1,
+        _ => 0,
+    });
+    return x;
+}
+
+// For these n is N/64, the length of the residues less one.
+#[test]
+fn ssa_mod_matches_gmp() {
+    for (i, &threshold) in [4, 16, 256].iter().enumerate() {
+        let mut lengths = Vec::new();
+        for &n in [1, 2, 3, 4, 5, 15, 16, 17, 64, 255, 256, 257, 1000, 1024].iter() {
+            lengths.extend(shape_pairs().into_iter().map(|(a, b)| (n, n, a, b)));
+        }
+        let base = 0xb000_0000 * (i as u64 + 1);
+        let mut cases = fixed(base, &lengths);
+        cases.extend(random(base, |seed| {
+            let case = random_lengths(seed, 2048);
+            Case {m: case.n, ..case}
+        }));
+        let mut ssa = Ssa::with_threshold(threshold);
+        run(&format!("Ssa mod, threshold {}", threshold), &cases, |case| {
+            let mut state = case.seed | 1;
+            let a = ssa_residue(case.n, case.a, &mut state);
+            let b = ssa_residue(case.n, case.b, &mut state);
+            let mut modulus = vec![0u64; case.n + 1];
+            modulus[0] = 1;
+            modulus[case.n] = 1;
+            let mut out = vec![u64::MAX; case.n + 1];
+            ssa.mul_mod(&mut out, &a, &b, 64 * case.n);
+            assert_eq!(out, gmp_rem(&gmp_mul(&a, &b), &modulus), "mul_mod");
+            ssa.sqr_mod(&mut out, &a, 64 * case.n);
+            assert_eq!(out, gmp_rem(&gmp_sqr(&a), &modulus), "sqr_mod");
+        });
+    }
+}
+
+// Below a Dwt's modulus N, with Ones for N - 1.
+fn dwt_residue(modulus: &[u64], shape: Shape, state: &mut u64) -> Vec<u64> {
+    if let Shape::Ones = shape {
+        let mut x = modulus.to_vec();
+        x[0] -= 1;
+        return x;
+    }
+    return gmp_rem(&operand(modulus.len(), shape, state), modulus);
+}
+
+// The biggest k that Dwt takes with n, since there's a Dwt for every k
+// up to one too big for double precision.
+fn largest_k(n: usize) -> u64 {
+    let (mut lo, mut hi) = (1u64, 1u64 << 32);
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        match Dwt::validate(mid, n) {
+            Ok(()) => lo = mid,
+            Err(_) => hi = mid,
+        }
+    }
+    return lo;
+}
+
+// For these n is the exponent and m is k, of N = k*2^n+1. The fixed ones
+// are either side of powers of two, where the words run out of bits and
+// the length stops, with k from small up to the biggest there is.
+#[test]
+fn dwt_matches_gmp() {
+    let mut pairs = vec![(1, 4096), (45, 10000), (65535, 4000), (3, 64 * 300)];
+    for &p in [2usize, 4, 8, 64, 128, 1024, 4096].iter() {
+        for n in (p - 1).max(2)..=p + 1 {
+            let top = largest_k(n);
+            assert!(Dwt::validate(top + 1, n).is_err());
+            pairs.extend([1, 3, top / 2 + 1, top - 1, top].iter().filter(|&&k| k >= 1).map(|&k| (k, n)));
+        }
+    }
+    let mut lengths = Vec::new();
+    for &(k, n) in pairs.iter() {
+        lengths.extend(shape_pairs().into_iter().map(|(a, b)| (n, k as usize, a, b)));
+    }
+    let mut cases = fixed(0xd000_0000, &lengths);
+    // k from 1 to 2^24 and past, which Dwt turns down as too big
+    cases.extend(random(0xd000_0000, |seed| {
+        let mut state = seed | 1;
+        let case = random_lengths(seed, 20000);
+        let k = 1 + next(&mut state) % (1 << (next(&mut state) % 26));
+        Case {n: case.n + 1, m: k as usize, ..case}
+    }));
+    run("Dwt", &cases, |case| {
+        if let Err(e) = Dwt::validate(case.m as u64, case.n) {
+            assert!(case.m as u64 > largest_k(case.n), "{}", e);
+            return;
+        }
+        let mut dwt = Dwt::new(case.m as u64, case.n);
+        let modulus = dwt.modulus().to_vec();
+        let mut state = case.seed | 1;
+        let a = dwt_residue(&modulus, case.a, &mut state);
+        let b = dwt_residue(&modulus, case.b, &mut state);
+        let (mut x, y) = (dwt.residue(&a), dwt.residue(&b));
+        dwt.mul(&mut x, &y);
+        let ab = gmp_rem(&gmp_mul(&a, &b), &modulus);
+        assert_eq!(dwt.to_limbs(&x), ab, "mul");
+        dwt.sqr(&mut x);
+        assert_eq!(dwt.to_limbs(&x), gmp_rem(&gmp_sqr(&ab), &modulus), "sqr");
+        assert!(dwt.last_error() <= dwt.error_limit());
+    });
+}
@@ -0,0 +1,596 @@
+#![warn(rust_2018_idioms)]
+
+// Multiplication modulo N = k*2^n+1 by irrational-base discrete weighted
+// transform, so the product comes out already reduced instead of twice as
+// long.
+//
+// A residue is L words x_j at bit positions p_j = ceil(n j / L), so the
+// words are floor(n / L) or ceil(n / L) bits. Weighting word j by
+// a_j = 2^(p_j - n j / L) makes that look like the even base 2^(n/L), and
+// a negacyclic convolution then wraps x_i y_j with i + j >= L around to
+// the bottom times -2^n. Here 2^n is -1/k, not -1, so the weights also
+// carry k^(-j/L), which makes the wrapped terms come out divided by k.
+//
+// That's the k-scaling: multiplied by k, every output word is an integer
+// again, and the words make up k x y. Residues are kept as x/k, so
+// k (x/k) (y/k) is xy/k and a product stays in the same form.
+//
+// The negacyclic convolution of L real words is a cyclic one of L/2
+// complex words, x_j + i x_(j+L/2) twisted by e^(i pi j/L), done with the
+// transforms in fft.rs. Round-off is checked like Fft does, and past the
+// limit the product is redone with twice as many words.
+
+use std::cmp::Ordering;
+
+use crate::fft::{forward, inverse, roots, Complex, ERROR_LIMIT};
+use crate::limbs::{add_to, sub_from, cmp, divrem_small, mul_small, normalized_len};
+use crate::threads;
+
+// Where the words are and what they're weighted with, for L words.
+struct Layout {
+    pos: Vec<usize>,
+    weight: Vec<f64>,
+    // k / (a_j L/2), from the inverse transform back to k times the word
+    unweight: Vec<f64>,
+    twist: Vec<Complex>,
+    roots: Vec<Complex>,
+}
+
+impl Layout {
+    fn new(k: u64, n: usize, len: usize) -> Self {
+        let half = len / 2;
+        let pos: Vec<usize> = (0..=len).map(|j| (n * j).div_ceil(len)).collect();
+        let weight: Vec<f64> = (0..len).map(|j| {
+            let frac = pos[j] as f64 - (n * j) as f64 / len as f64;
+            let kpow = (k as f64).powf(-(j as f64) / len as f64);
+            (2.0f64).powf(frac) * kpow
+        }).collect();
+        let unweight = weight.iter().map(|&a| k as f64 / (a * half as f64)).collect();
+        let twist = (0..half).map(|j| Complex::expi(std::f64::consts::PI * j as f64 / len as f64)).collect();
+        Layout {pos, weight, unweight, twist, roots: roots(half)}
+    }
+
+    fn len(&self) -> usize {
+        self.weight.len()
+    }
+
+    fn width(&self, j: usize) -> u32 {
+        (self.pos[j + 1] - self.pos[j]) as u32
+    }
+}
+
+// The most bits per word that should stay inside the error limit, like
+// Fft's digits but with log2 k taken off twice: the outputs are scaled up
+// by k, and the top ones by nearly k again for their weights, while the
+// round-off is as big as the biggest outputs' everywhere.
+fn safe_bits(log_len: u32, k: u64) -> f64 {
+    return ((48.0 - log_len as f64 / 2.0 - 2.0 * (k as f64).log2()) / 2.0).floor();
+}
+
+// The fewest words for k*2^n+1 that are within safe_bits, as log2 L.
+// Every word needs a bit, so L stops at the largest power of two up to n.
+fn log_len(k: u64, n: usize) -> Result<u32, String> {
+    if k == 0 || n < 2 {
+        return Err(format!("no Dwt for k = {}, n = {}", k, n));
+    }
+    let mut log_len = 1;
+    while (2 << log_len) <= n && n.div_ceil(1 << log_len) as f64 > safe_bits(log_len, k) {
+        log_len += 1;
+    }
+    if n.div_ceil(1 << log_len) as f64 > safe_bits(log_len, k) {
+        return Err(format!("k = {} is too big for double precision with n = {}", k, n));
+    }
+    return Ok(log_len);
+}
+
+// A residue modulo a Dwt's N, as x/k in words, see above. Only meaningful
+// to the Dwt that made it.
+#[derive(Clone, Debug)]
+pub struct DwtResidue {
+    words: Vec<i64>,
+}
+
+pub struct Dwt {
+    k: u64,
+    n: usize,
+    modulus: Vec<u64>,
+    limit: f64,
+    layout: Layout,
+    u: Vec<Complex>,
+    v: Vec<Complex>,
+    z: Vec<i64>,
+    last_error: f64,
+    max_error: f64,
+}
+
+impl Dwt {
+    pub fn new(k: u64, n: usize) -> Self {
+        Self::with_error_limit(k, n, ERROR_LIMIT)
+    }
+
+    pub fn with_error_limit(k: u64, n: usize, limit: f64) -> Self {
+        assert!(k >= 1 && n >= 2);
+        assert!(limit > 0.0 && limit <= 0.5, "error limit must be in (0, 0.5]");
+        let mut modulus = vec![0u64; (n + 64 - (k.leading_zeros() as usize)) / 64 + 1];
+        add_shifted(&mut modulus, k, n);
+        add_to(&mut modulus, &[1]);
+        let log_len = match log_len(k, n) {
+            Ok(log_len) => log_len,
+            Err(e) => panic!("{}", e),
+        };
+        let mut dwt = Dwt {
+            k,
+            n,
+            modulus,
+            limit,
+            layout: Layout::new(k, n, 1 << log_len),
+            u: Vec::new(),
+            v: Vec::new(),
+            z: Vec::new(),
+            last_error: 0.0,
+            max_error: 0.0,
+        };
+        dwt.resize();
+        return dwt;
+    }
+
+    // Whether there's a Dwt for k*2^n+1, which there isn't when even one
+    // bit per word is too many for double precision.
+    pub fn validate(k: u64, n: usize) -> Result<(), String> {
+        return log_len(k, n).map(|_| ());
+    }
+
+    // k*2^n+1, in as many limbs as the residues come out in.
+    pub fn modulus(&self) -> &[u64] {
+        &self.modulus
+    }
+
+    // L, the number of words.
+    pub fn words(&self) -> usize {
+        self.layout.len()
+    }
+
+    pub fn error_limit(&self) -> f64 {
+        self.limit
+    }
+
+    // The round-off error of the last product, and the worst of all of
+    // them, fallbacks included.
+    pub fn last_error(&self) -> f64 {
+        self.last_error
+    }
+
+    pub fn max_error(&self) -> f64 {
+        self.max_error
+    }
+
+    // x mod N, for x at most twice as long as N.
+    pub fn residue(&self, x: &[u64]) -> DwtResidue {
+        // x/k, with 1/k = (k-1)*2^n + 1 mod N
+        let mut t = vec![0u64; 2 * self.modulus.len() + 1];
+        t[..x.len()].copy_from_slice(x);
+        let mut hi = vec![0u64; 2 * self.modulus.len() + 1];
+        hi[..x.len()].copy_from_slice(x);
+        let top = mul_small(&mut hi, self.k - 1);
+        debug_assert_eq!(top, 0);
+        let hi = self.reduce(hi);
+        let mut shifted = vec![0u64; 2 * self.modulus.len() + 1];
+        for (i, &h) in hi.iter().enumerate() {
+            add_shifted(&mut shifted, h, 64 * i + self.n);
+        }
+        add_to(&mut t, &self.reduce(shifted));
+        return DwtResidue {words: self.to_words(&self.reduce(t))};
+    }
+
+    // The residue as limbs, from 0 to N - 1, as long as modulus().
+    pub fn to_limbs(&self, x: &DwtResidue) -> Vec<u64> {
+        let mut t = self.value(&x.words);
+        t.push(0);
+        let top = mul_small(&mut t, self.k);
+        debug_assert_eq!(top, 0);
+        return self.reduce(t);
+    }
+
+    // x = x * y mod N
+    pub fn mul(&mut self, x: &mut DwtResidue, y: &DwtResidue) {
+        self.product(x, Some(y));
+    }
+
+    // x = x * x mod N
+    pub fn sqr(&mut self, x: &mut DwtResidue) {
+        self.product(x, None);
+    }
+
+    fn resize(&mut self) {
+        let len = self.layout.len();
+        self.u.resize(len / 2, Complex::default());
+        self.v.resize(len / 2, Complex::default());
+        self.z.resize(len, 0);
+    }
+
+    fn product(&mut self, x: &mut DwtResidue, y: Option<&DwtResidue>) {
+        loop {
+            self.relayout(x);
+            let error = match y {
+                Some(y) if y.words.len() != self.words() => {
+                    let mut y = y.clone();
+                    self.relayout(&mut y);
+                    self.transform(x, Some(&y))
+                }
+                _ => self.transform(x, y),
+            };
+            self.last_error = error;
+            if error > self.max_error {
+                self.max_error = error;
+            }
+            if error <= self.limit {
+                self.carry();
+                x.words.copy_from_slice(&self.z);
+                return;
+            }
+            let len = 2 * self.words();
+            assert!(len <= self.n, "round-off error {} past {} with one bit per word", error, self.limit);
+            self.layout = Layout::new(self.k, self.n, len);
+            self.resize();
+        }
+    }
+
+    // Moves a residue made before a fallback to the current layout.
+    fn relayout(&self, x: &mut DwtResidue) {
+        if x.words.len() != self.words() {
+            x.words = self.to_words(&self.value(&x.words));
+        }
+    }
+
+    fn value(&self, words: &[i64]) -> Vec<u64> {
+        return self.reduce(self.value_with(words));
+    }
+
+    // One attempt at the product, leaving k times the output words in z
+    // and returning the round-off error.
+    fn transform(&mut self, x: &DwtResidue, y: Option<&DwtResidue>) -> f64 {
+        let layout = &self.layout;
+        let half = layout.len() / 2;
+        let load = |u: &mut [Complex], w: &[i64]| {
+            for (j, c) in u.iter_mut().enumerate() {
+                let re = w[j] as f64 * layout.weight[j];
+                let im = w[j + half] as f64 * layout.weight[j + half];
+                *c = Complex::new(re, im) * layout.twist[j];
+            }
+        };
+        let (u, v) = (&mut self.u, &mut self.v);
+        match y {
+            Some(y) => {
+                // The two transforms are independent.
+                let mut one = || {
+                    load(u, &x.words);
+                    forward(u, &layout.roots);
+                };
+                let mut other = || {
+                    load(v, &y.words);
+                    forward(v, &layout.roots);
+                };
+                if threads::parallel_len(half) {
+                    rayon::join(one, other);
+                } else {
+                    one();
+                    other();
+                }
+                for (u, &v) in u.iter_mut().zip(v.iter()) {
+                    *u = *u * v;
+                }
+            }
+            None => {
+                load(u, &x.words);
+                forward(u, &layout.roots);
+                for u in u.iter_mut() {
+                    *u = *u * *u;
+                }
+            }
+        }
+        inverse(&mut self.u, &layout.roots);
+        let mut error: f64 = 0.0;
+        let mut round = |t: f64| {
+            let r = t.round();
+            error = error.max((t - r).abs());
+            r as i64
+        };
+        for (j, &u) in self.u.iter().enumerate() {
+            let c = u * layout.twist[j].conj();
+            self.z[j] = round(c.re * layout.unweight[j]);
+            self.z[j + half] = round(c.im * layout.unweight[j + half]);
+        }
+        return error;
+    }
+
+    // Carries z into balanced words, all but the top one between -2^(b-1)
+    // and 2^(b-1) for their b bits. Whatever carries out of the top is c
+    // times 2^n, which is (c mod k) 2^n - c div k since k 2^n = -1, so the
+    // top word keeps the first part and the bottom takes the second. That
+    // leaves the top word from 0 to k 2^b, inclusive: without k 2^b the
+    // words only reach k 2^n values, one short of N, and a product that
+    // came to the missing one would carry round for ever.
+    fn carry(&mut self) {
+        let layout = &self.layout;
+        let top = layout.len() - 1;
+        let k = self.k as i128;
+        loop {
+            let mut c: i128 = 0;
+            for j in 0..top {
+                let b = layout.width(j);
+                let v = self.z[j] as i128 + c;
+                let half = 1i128 << (b - 1);
+                let d = ((v + half) & ((1i128 << b) - 1)) - half;
+                c = (v - d) >> b;
+                self.z[j] = d as i64;
+            }
+            let b = layout.width(top);
+            let v = self.z[top] as i128 + c;
+            if v == k << b {
+                self.z[top] = v as i64;
+                return;
+            }
+            let hi = v >> b;
+            let lo = v - (hi << b);
+            self.z[top] = (lo + (hi.rem_euclid(k) << b)) as i64;
+            let q = hi.div_euclid(k);
+            if q == 0 {
+                return;
+            }
+            self.z[0] -= q as i64;
+        }
+    }
+
+    // The words of x, which is from 0 to N - 1.
+    fn to_words(&self, x: &[u64]) -> Vec<i64> {
+        let layout = &self.layout;
+        let top = layout.len() - 1;
+        let mut words = vec![0i64; layout.len()];
+        let mut c = 0;
+        for (j, w) in words.iter_mut().enumerate() {
+            if j == top {
+                *w = bits_at(x, layout.pos[j], 64) as i64 + c;
+                break;
+            }
+            let b = layout.width(j);
+            let mut d = bits_at(x, layout.pos[j], b) as i64 + c;
+            c = 0;
+            if d >= 1 << (b - 1) {
+                d -= 1 << b;
+                c = 1;
+            }
+            *w = d;
+        }
+        return words;
+    }
+
+    // sum x_j 2^(p_j), not reduced.
+    fn value_with(&self, words: &[i64]) -> Vec<u64> {
+        let len = self.modulus.len() + 2;
+        let (mut plus, mut minus) = (vec![0u64; len], vec![0u64; len]);
+        for (&w, &p) in words.iter().zip(self.layout_for(words.len()).iter()) {
+            match w.cmp(&0) {
+                Ordering::Greater => add_shifted(&mut plus, w as u64, p),
+                Ordering::Less => add_shifted(&mut minus, w.unsigned_abs(), p),
+                Ordering::Equal => (),
+            }
+        }
+        if cmp(&plus, &minus) != Ordering::Less {
+            sub_from(&mut plus, &minus);
+            return plus;
+        }
+        // N - ((minus - plus) mod N)
+        sub_from(&mut minus, &plus);
+        let mut r = self.modulus.clone();
+        r.resize(len, 0);
+        let m = self.reduce(minus);
+        if normalized_len(&m) != 0 {
+            sub_from(&mut r, &m);
+        }
+        return r;
+    }
+
+    // The word positions for residues of len words, which are the current
+    // layout's unless they're left over from before a fallback.
+    fn layout_for(&self, len: usize) -> Vec<usize> {
+        if len == self.words() {
+            return self.layout.pos.clone();
+        }
+        return (0..=len).map(|j| (self.n * j).div_ceil(len)).collect();
+    }
+
+    // y mod N, for y up to about N^2. With y = hi 2^n + lo and
+    // hi = q k + r, y = r 2^n + lo - q.
+    fn reduce(&self, y: Vec<u64>) -> Vec<u64> {
+        let len = self.modulus.len();
+        let mut hi = shr(&y, self.n);
+        let r = divrem_small(&mut hi, self.k);
+        let mut t = vec![0u64; len + 1];
+        let lo = low_bits(&y, self.n);
+        t[..lo.len().min(len + 1)].copy_from_slice(&lo[..lo.len().min(len + 1)]);
+        add_shifted(&mut t, r, self.n);
+        while cmp(&t, &hi) == Ordering::Less {
+            add_to(&mut t, &self.modulus);
+        }
+        sub_from(&mut t, &hi[..normalized_len(&hi)]);
+        while cmp(&t, &self.modulus) != Ordering::Less {
+            sub_from(&mut t, &self.modulus);
+        }
+        t.truncate(len);
+        return t;
+    }
+}
+
+// The bits of a from pos to pos + bits, bits at most 64.
+fn bits_at(a: &[u64], pos: usize, bits: u32) -> u64 {
+    let (i, shift) = (pos / 64, (pos % 64) as u32);
+    let lo = a.get(i).cloned().unwrap_or(0) as u128;
+    let hi = a.get(i + 1).cloned().unwrap_or(0) as u128;
+    let window = ((lo | hi << 64) >> shift) as u64;
+    if bits >= 64 {
+        return window;
+    }
+    return window & ((1 << bits) - 1);
+}
+
+// out += v 2^pos
+fn add_shifted(out: &mut [u64], v: u64, pos: usize) {
+    let (i, shift) = (pos / 64, pos % 64);
+    let t = (v as u128) << shift;
+    let end = (out.len() - i).min(2);
+    let carry = add_to(&mut out[i..], &[t as u64, (t >> 64) as u64][..end]);
+    debug_assert!(!carry);
+}
+
+// y >> s
+fn shr(y: &[u64], s: usize) -> Vec<u64> {
+    let (i, shift) = (s / 64, (s % 64) as u32);
+    let mut out: Vec<u64> = y.iter().skip(i).cloned().collect();
+    if shift != 0 {
+        for j in 0..out.len() {
+            let next = out.get(j + 1).cloned().unwrap_or(0);
+            out[j] = (out[j] >> shift) | (next << (64 - shift));
+        }
+    }
+    out.push(0);
+    return out;
+}
+
+// y mod 2^s
+fn low_bits(y: &[u64], s: usize) -> Vec<u64> {
+    let (i, shift) = (s / 64, s % 64);
+    let mut out: Vec<u64> = y.iter().take(i + 1).cloned().collect();
+    if out.len() > i {
+        out[i] &= (1u64 << shift) - 1;
+    }
+    return out;
+}
+
+// tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{gmp_mul, gmp_rem, next};
+
+    // x * y mod k*2^n+1 by GMP, as limbs as long as the modulus.
+    fn gmp_mulmod(x: &[u64], y: &[u64], modulus: &[u64]) -> Vec<u64> {
+        return gmp_rem(&gmp_mul(x, y), modulus);
+    }
+
+    // Something below N, with the top limbs cut down to fit.
+    fn below(modulus: &[u64], state: &mut u64) -> Vec<u64> {
+        let mut x: Vec<u64> = modulus.iter().map(|_| next(state)).collect();
+        let top = normalized_len(modulus) - 1;
+        x[top] %= modulus[top];
+        for t in x.iter_mut().skip(top + 1) {
+            *t = 0;
+        }
+        return x;
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut state = 5;
+        for &(k, n) in [(1, 2), (3, 5), (5, 64), (3, 127), (13, 1000), (65535, 4000)].iter() {
+            let dwt = Dwt::new(k, n);
+            for _ in 0..20 {
+                let x = below(dwt.modulus(), &mut state);
+                assert_eq!(dwt.to_limbs(&dwt.residue(&x)), x, "k {} n {}", k, n);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_gmp() {
+        let mut state = 0x2545_f491_4f6c_dd1d;
+        for &(k, n) in [(1, 16), (3, 18), (5, 100), (7, 1000), (1, 4096), (45, 10000), (3, 100_000), (9999, 40000)].iter() {
+            let mut dwt = Dwt::new(k, n);
+            let modulus = dwt.modulus().to_vec();
+            let a = below(&modulus, &mut state);
+            let b = below(&modulus, &mut state);
+            let (mut x, y) = (dwt.residue(&a), dwt.residue(&b));
+            dwt.mul(&mut x, &y);
+            let ab = gmp_mulmod(&a, &b, &modulus);
+            assert_eq!(dwt.to_limbs(&x), ab, "k {} n {}", k, n);
+            dwt.sqr(&mut x);
+            assert_eq!(dwt.to_limbs(&x), gmp_mulmod(&ab, &ab, &modulus), "k {} n {}", k, n);
+            assert!(dwt.max_error() <= dwt.error_limit());
+            // N - 1 = -1 squares to 1
+            let mut minus_one = modulus.clone();
+            sub_from(&mut minus_one, &[1]);
+            let mut x = dwt.residue(&minus_one);
+            dwt.sqr(&mut x);
+            let mut one = vec![0u64; modulus.len()];
+            one[0] = 1;
+            assert_eq!(dwt.to_limbs(&x), one);
+        }
+    }
+
+    #[test]
+    fn small_moduli_exhaustively() {
+        for k in 1..=5 {
+            for n in 2..=6 {
+                let mut dwt = Dwt::new(k, n);
+                let modulus = dwt.modulus().to_vec();
+                for a in 0..modulus[0] {
+                    for b in 0..modulus[0] {
+                        let (mut x, y) = (dwt.residue(&[a]), dwt.residue(&[b]));
+                        dwt.mul(&mut x, &y);
+                        assert_eq!(dwt.to_limbs(&x), gmp_mulmod(&[a], &[b], &modulus), "k {} n {} {} {}", k, n, a, b);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn large_k_small_n() {
+        // One bit per word is too many for these.
+        for &(k, n) in [(1048575, 1000), (1000000, 600), (1 << 24, 100_000), (u32::MAX as u64, 5)].iter() {
+            assert!(Dwt::validate(k, n).unwrap_err().contains("too big"), "k {} n {}", k, n);
+        }
+        assert!(Dwt::validate(0, 10).is_err() && Dwt::validate(3, 1).is_err());
+        let mut state = 41;
+        for &(k, n) in [(524289, 1000), (999999, 2), (1000003, 5), (65537, 64), (65535, 63), (4095, 129)].iter() {
+            assert_eq!(Dwt::validate(k, n), Ok(()));
+            let mut dwt = Dwt::new(k, n);
+            assert!(dwt.words() <= n, "k {} n {}", k, n);
+            let modulus = dwt.modulus().to_vec();
+            for _ in 0..10 {
+                let a = below(&modulus, &mut state);
+                let b = below(&modulus, &mut state);
+                let (mut x, y) = (dwt.residue(&a), dwt.residue(&b));
+                dwt.mul(&mut x, &y);
+                assert_eq!(dwt.to_limbs(&x), gmp_mulmod(&a, &b, &modulus), "k {} n {}", k, n);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "too big for double precision")]
+    fn too_big() {
+        Dwt::new(1048575, 1000);
+    }
+
+    #[test]
+    fn falls_back_to_more_words() {
+        let mut state = 17;
+        let mut dwt = Dwt::with_error_limit(3, 50000, 1e-7);
+        let before = dwt.words();
+        let modulus = dwt.modulus().to_vec();
+        let a = below(&modulus, &mut state);
+        let b = below(&modulus, &mut state);
+        let mut x = dwt.residue(&a);
+        let y = dwt.residue(&b);
+        dwt.mul(&mut x, &y);
+        assert_eq!(dwt.to_limbs(&x), gmp_mulmod(&a, &b, &modulus));
+        assert!(dwt.words() > before);
+        assert!(dwt.max_error() > dwt.error_limit());
+        assert!(dwt.last_error() <= dwt.error_limit());
+        // y is still in the old layout
+        dwt.mul(&mut x, &y);
+        let ab = gmp_mulmod(&a, &b, &modulus);
+        assert_eq!(dwt.to_limbs(&x), gmp_mulmod(&ab, &b, &modulus));
+    }
+}
@@ -0,0 +1,469 @@
+#![warn(rust_2018_idioms)]
+
+// Multiplication by double-precision FFT, for operands in the millions of
+// bits. The operands are cut into balanced digits, digits of b bits
+// between -2^(b-1) and 2^(b-1), which keeps the convolution sums, and so
+// the round-off, smaller than plain digits would. The transform is a
+// complex radix-4 FFT with a radix-2 pass for odd powers of two.
+//
+// The result of every product is checked: each coefficient should come
+// out of the inverse transform within round-off of an integer, and how
+// far the worst one is from it is the round-off error. Past the error
+// limit the product is thrown away and done again with fewer bits per
+// digit, which is a longer transform, and the Fft stays at that size.
+
+use std::f64::consts::PI;
+use crate::threads;
+use std::ops::{Add, Mul, Sub};
+
+// Coefficients within this of an integer are trusted. At 0.5 the rounding
+// could already have gone the wrong way.
+pub(crate) const ERROR_LIMIT: f64 = 0.4;
+
+// The most bits per digit before the error check has a say.
+const MAX_DIGIT_BITS: u32 = 24;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Complex {
+    pub(crate) re: f64,
+    pub(crate) im: f64,
+}
+
+impl Complex {
+    pub(crate) fn new(re: f64, im: f64) -> Self {
+        Complex {re, im}
+    }
+
+    // e^(i theta)
+    pub(crate) fn expi(theta: f64) -> Self {
+        let (s, c) = theta.sin_cos();
+        Complex {re: c, im: s}
+    }
+
+    pub(crate) fn conj(self) -> Self {
+        Complex {re: self.re, im: -self.im}
+    }
+
+    pub(crate) fn mul_i(self) -> Self {
+        Complex {re: -self.im, im: self.re}
+    }
+
+    pub(crate) fn mul_neg_i(self) -> Self {
+        Complex {re: self.im, im: -self.re}
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, o: Complex) -> Complex {
+        Complex {re: self.re + o.re, im: self.im + o.im}
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, o: Complex) -> Complex {
+        Complex {re: self.re - o.re, im: self.im - o.im}
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, o: Complex) -> Complex {
+        Complex {
+            re: self.re * o.re - self.im * o.im,
+            im: self.re * o.im + self.im * o.re,
+        }
+    }
+}
+
+// A transform length and digit size, with the roots of unity for it.
+struct Plan {
+    bits: u32,
+    roots: Vec<Complex>,
+}
+
+impl Plan {
+    fn new(bits: u32, len: usize) -> Self {
+        Plan {bits, roots: roots(len)}
+    }
+
+    fn len(&self) -> usize {
+        self.roots.len()
+    }
+}
+
+// roots[t] = e^(-2 pi i t / len), each straight from sin_cos so the error
+// doesn't build up along the table.
+pub(crate) fn roots(len: usize) -> Vec<Complex> {
+    (0..len).map(|t| Complex::expi(-2.0 * PI * t as f64 / len as f64)).collect()
+}
+
+// Bits per digit that should keep a transform of 2^log_len well inside the
+// error limit: the products of two digits are 2b - 2 bits and the sums of
+// them grow like the square root of the length for random digits.
+fn safe_bits(log_len: u32) -> u32 {
+    (48 - log_len / 2) / 2
+}
+
+pub struct Fft {
+    limit: f64,
+    // fewer bits than this per digit, lowered by each fallback
+    bits_cap: u32,
+    plan: Option<Plan>,
+    x: Vec<Complex>,
+    y: Vec<Complex>,
+    last_error: f64,
+    max_error: f64,
+}
+
+impl Default for Fft {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fft {
+    pub fn new() -> Self {
+        Self::with_error_limit(ERROR_LIMIT)
+    }
+
+    pub fn with_error_limit(limit: f64) -> Self {
+        assert!(limit > 0.0 && limit <= 0.5, "error limit must be in (0, 0.5]");
+        Fft {
+            limit,
+            bits_cap: MAX_DIGIT_BITS,
+            plan: None,
+            x: Vec::new(),
+            y: Vec::new(),
+            last_error: 0.0,
+            max_error: 0.0,
+        }
+    }
+
+    pub fn error_limit(&self) -> f64 {
+        self.limit
+    }
+
+    // The round-off error of the last product, and the worst of all of
+    // them, fallbacks included.
+    pub fn last_error(&self) -> f64 {
+        self.last_error
+    }
+
+    pub fn max_error(&self) -> f64 {
+        self.max_error
+    }
+
+    // The digit size and transform length of the last product.
+    pub fn digit_bits(&self) -> u32 {
+        self.plan.as_ref().map_or(0, |p| p.bits)
+    }
+
+    pub fn transform_len(&self) -> usize {
+        self.plan.as_ref().map_or(0, |p| p.len())
+    }
+
+    // out = a * b in the low a.len() + b.len() limbs of out.
+    pub fn mul(&mut self, out: &mut [u64], a: &[u64], b: &[u64]) {
+        assert!(out.len() >= a.len() + b.len());
+        self.product(&mut out[..a.len() + b.len()], a, Some(b));
+    }
+
+    // out = a * a in the low 2 * a.len() limbs of out.
+    pub fn sqr(&mut self, out: &mut [u64], a: &[u64]) {
+        assert!(out.len() >= 2 * a.len());
+        self.product(&mut out[..2 * a.len()], a, None);
+    }
+
+    fn product(&mut self, out: &mut [u64], a: &[u64], b: Option<&[u64]>) {
+        let b_len = b.map_or(a.len(), |b| b.len());
+        if a.is_empty() || b_len == 0 {
+            for x in out.iter_mut() {
+                *x = 0;
+            }
+            return;
+        }
+        loop {
+            self.plan_for(64 * a.len(), 64 * b_len);
+            let error = self.transform(out, a, b);
+            self.last_error = error;
+            if error > self.max_error {
+                self.max_error = error;
+            }
+            if error <= self.limit {
+                return;
+            }
+            let bits = self.digit_bits();
+            assert!(bits > 1, "round-off error {} past {} even with 1-bit digits", error, self.limit);
+            self.bits_cap = bits - 1;
+        }
+    }
+
+    // Picks the biggest digits that are safe for the length they need,
+    // and keeps the current plan if it's the same.
+    fn plan_for(&mut self, a_bits: usize, b_bits: usize) {
+        let mut log_len = 1;
+        let bits = loop {
+            let bits = safe_bits(log_len).min(self.bits_cap);
+            // the balanced top digit can carry into one more
+            let digits = a_bits / bits as usize + b_bits / bits as usize + 4;
+            if digits <= 1 << log_len {
+                break bits;
+            }
+            log_len += 1;
+        };
+        let len = 1 << log_len;
+        if self.digit_bits() != bits || self.transform_len() != len {
+            self.plan = Some(Plan::new(bits, len));
+            self.x.resize(len, Complex::default());
+            self.y.resize(len, Complex::default());
+        }
+    }
+
+    // One attempt at the product with the current plan, returning its
+    // round-off error.
+    fn transform(&mut self, out: &mut [u64], a: &[u64], b: Option<&[u64]>) -> f64 {
+        let plan = self.plan.as_ref().unwrap();
+        let (x, y) = (&mut self.x, &mut self.y);
+        let (bits, roots) = (plan.bits, &plan.roots);
+        let par = threads::parallel_len(x.len());
+        match b {
+            Some(b) => {
+                // The two transforms are independent.
+                let mut one = || {
+                    to_digits(x, a, bits);
+                    forward(x, roots);
+                };
+                let mut other = || {
+                    to_digits(y, b, bits);
+                    forward(y, roots);
+                };
+                if par {
+                    rayon::join(one, other);
+                } else {
+                    one();
+                    other();
+                }
+                for (x, &y) in self.x.iter_mut().zip(self.y.iter()) {
+                    *x = *x * y;
+                }
+            }
+            None => {
+                to_digits(x, a, bits);
+                forward(x, roots);
+                for x in x.iter_mut() {
+                    *x = *x * *x;
+                }
+            }
+        }
+        inverse(&mut self.x, roots);
+        return from_digits(out, &self.x, bits);
+    }
+}
+
+// The bits of a from pos to pos + bits, bits at most 32.
+fn bits_at(a: &[u64], pos: usize, bits: u32) -> u64 {
+    let (i, shift) = (pos / 64, (pos % 64) as u32);
+    let lo = a.get(i).cloned().unwrap_or(0) as u128;
+    let hi = a.get(i + 1).cloned().unwrap_or(0) as u128;
+    let window = (lo | hi << 64) >> shift;
+    return (window as u64) & ((1 << bits) - 1);
+}
+
+// Fills x with the balanced digits of a, zero past the top.
+fn to_digits(x: &mut [Complex], a: &[u64], bits: u32) {
+    let (half, base) = (1i64 << (bits - 1), 1i64 << bits);
+    let mut carry = 0;
+    for (i, d) in x.iter_mut().enumerate() {
+        let mut v = bits_at(a, i * bits as usize, bits) as i64 + carry;
+        carry = 0;
+        if v >= half {
+            v -= base;
+            carry = 1;
+        }
+        *d = Complex {re: v as f64, im: 0.0};
+    }
+    debug_assert_eq!(carry, 0);
+}
+
+// Rounds the inverse transform back to integers, carrying from each
+// digit into the next, and writes them to out. Returns the round-off
+// error.
+fn from_digits(out: &mut [u64], x: &[Complex], bits: u32) -> f64 {
+    let scale = 1.0 / x.len() as f64;
+    let mask = (1i128 << bits) - 1;
+    let mut error: f64 = 0.0;
+    let mut carry: i128 = 0;
+    let (mut acc, mut acc_bits, mut w) = (0u128, 0u32, 0);
+    let mut put = |acc: &mut u128, acc_bits: &mut u32| {
+        while *acc_bits >= 64 {
+            match out.get_mut(w) {
+                Some(o) => *o = *acc as u64,
+                None => debug_assert_eq!(*acc as u64, 0),
+            }
+            w += 1;
+            *acc >>= 64;
+            *acc_bits -= 64;
+        }
+    };
+    for c in x.iter() {
+        let v = c.re * scale;
+        let r = v.round();
+        error = error.max((v - r).abs());
+        carry += r as i128;
+        acc |= ((carry & mask) as u128) << acc_bits;
+        acc_bits += bits;
+        carry >>= bits;
+        put(&mut acc, &mut acc_bits);
+    }
+    // The product is positive, so whatever carry is left is too.
+    while carry > 0 {
+        acc |= ((carry & mask) as u128) << acc_bits;
+        acc_bits += bits;
+        carry >>= bits;
+        put(&mut acc, &mut acc_bits);
+    }
+    if acc_bits > 0 {
+        match out.get_mut(w) {
+            Some(o) => *o = acc as u64,
+            None => debug_assert_eq!(acc, 0),
+        }
+        w += 1;
+    }
+    for o in out.iter_mut().skip(w) {
+        *o = 0;
+    }
+    return error;
+}
+
+// Decimation in frequency from a length of x.len() down, with roots for
+// that length. The output is in a scrambled order that only inverse
+// needs to understand; the pointwise products don't care.
+pub(crate) fn forward(x: &mut [Complex], roots: &[Complex]) {
+    let n = x.len();
+    let par = threads::parallel_len(n);
+    let mut len = n;
+    while len >= 4 {
+        let stride = n / len;
+        threads::chunks(x, len, par, |block| threads::quarters(block, par, |j0, [b0, b1, b2, b3]| {
+            for j in 0..b0.len() {
+                let k = (j0 + j) * stride;
+                let (x0, x1, x2, x3) = (b0[j], b1[j], b2[j], b3[j]);
+                let (a, b) = (x0 + x2, x0 - x2);
+                let (c, d) = (x1 + x3, (x1 - x3).mul_neg_i());
+                b0[j] = a + c;
+                b1[j] = (b + d) * roots[k];
+                b2[j] = (a - c) * roots[2 * k];
+                b3[j] = (b - d) * roots[3 * k];
+            }
+        }));
+        len /= 4;
+    }
+    if len == 2 {
+        threads::chunks(x, 2, par, |pair| {
+            let (u, v) = (pair[0], pair[1]);
+            pair[0] = u + v;
+            pair[1] = u - v;
+        });
+    }
+}
+
+// forward backwards, each butterfly undone in the opposite order. Leaves
+// x.len() times the inverse transform.
+pub(crate) fn inverse(x: &mut [Complex], roots: &[Complex]) {
+    let n = x.len();
+    let par = threads::parallel_len(n);
+    let mut len = 4;
+    if n.trailing_zeros() % 2 == 1 {
+        threads::chunks(x, 2, par, |pair| {
+            let (u, v) = (pair[0], pair[1]);
+            pair[0] = u + v;
+            pair[1] = u - v;
+        });
+        len = 8;
+    }
+    while len <= n {
+        let stride = n / len;
+        threads::chunks(x, len, par, |block| threads::quarters(block, par, |j0, [b0, b1, b2, b3]| {
+            for j in 0..b0.len() {
+                let k = (j0 + j) * stride;
+                let y0 = b0[j];
+                let y1 = b1[j] * roots[k].conj();
+                let y2 = b2[j] * roots[2 * k].conj();
+                let y3 = b3[j] * roots[3 * k].conj();
+                let (a, c) = (y0 + y2, y0 - y2);
+                let (b, d) = (y1 + y3, (y1 - y3).mul_i());
+                b0[j] = a + b;
+                b1[j] = c + d;
+                b2[j] = a - b;
+                b3[j] = c - d;
+            }
+        }));
+        len *= 4;
+    }
+}
+
+// tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{gmp_mul, next, limbs, SIZES};
+
+    #[test]
+    fn round_trip() {
+        // Every length, even and odd powers of two, comes back to len * x.
+        let mut state = 3;
+        for log_len in 0..12 {
+            let len = 1 << log_len;
+            let plan = Plan::new(16, len);
+            let x: Vec<Complex> = (0..len).map(|_| Complex {re: (next(&mut state) % 1000) as f64, im: 0.0}).collect();
+            let mut y = x.clone();
+            forward(&mut y, &plan.roots);
+            inverse(&mut y, &plan.roots);
+            for (u, v) in x.iter().zip(y.iter()) {
+                assert!((u.re * len as f64 - v.re).abs() < 1e-6, "length {}", len);
+                assert!(v.im.abs() < 1e-6, "length {}", len);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_gmp() {
+        let mut state = 0x9e37_79b9_7f4a_7c15;
+        let mut fft = Fft::new();
+        for &(n, m) in SIZES.iter() {
+            let a = limbs(n, &mut state);
+            let b = limbs(m, &mut state);
+            let mut out = vec![0u64; n + m];
+            fft.mul(&mut out, &a, &b);
+            assert_eq!(out, gmp_mul(&a, &b), "{} x {}", n, m);
+            let mut sq = vec![0u64; 2 * n];
+            fft.sqr(&mut sq, &a);
+            assert_eq!(sq, gmp_mul(&a, &a), "{}^2", n);
+            assert!(fft.last_error() <= fft.error_limit());
+        }
+        assert!(fft.max_error() > 0.0);
+        // r may be longer than the product, the rest is left alone.
+        let mut r = [7u64; 5];
+        fft.mul(&mut r, &[3, 0], &[5, 1]);
+        assert_eq!(r, [15, 3, 0, 0, 7]);
+    }
+
+    #[test]
+    fn falls_back_to_a_longer_transform() {
+        let mut state = 11;
+        let a = limbs(2000, &mut state);
+        let mut fft = Fft::with_error_limit(1e-3);
+        let mut first = Fft::new();
+        let mut sq = vec![0u64; 4000];
+        first.sqr(&mut sq, &a);
+        fft.sqr(&mut sq, &a);
+        assert_eq!(sq, gmp_mul(&a, &a));
+        assert!(fft.max_error() > fft.error_limit());
+        assert!(fft.last_error() <= fft.error_limit());
+        assert!(fft.digit_bits() < first.digit_bits());
+        assert!(fft.transform_len() >= first.transform_len());
+    }
+}
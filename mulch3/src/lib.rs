@@ -1,3 +1,69 @@
 #![warn(rust_2018_idioms)]
 
+// Multiplication of limb slices. Limbs are u64, least significant first,
+// like GMP's on 64-bit machines. Small sizes use fully unrolled
+// schoolbook kernels, written out by build/main.rs for 1 to MAX_LIMBS
+// limbs, or a looped basecase; bigger ones Karatsuba and Toom-Cook, see
+// toom.rs. Fft in fft.rs is for the biggest, multi-million-bit operands,
+// and Dwt in dwt.rs multiplies modulo k*2^n+1 without the reduction. Ntt
+// in ntt.rs is the exact alternative to Fft, for checking it, and Ssa in
+// ssa.rs is Schönhage–Strassen, exact too and working modulo 2^N+1.
+// The big ones all spread over threads, see threads.rs. Where each
+// algorithm takes over is tuned per machine, see tune.rs.
+//
+// The no-bounds-checks feature drops the per-limb index checks in the
+// kernels, see access.rs, and on a nightly compiler the limb helpers use
+// AVX2 where there is one, see simd.rs.
+
+pub mod access;
+mod comba;
+mod basecase;
+mod limbs;
+mod simd;
+mod toom;
+mod fft;
+mod dwt;
+mod ntt;
+mod ssa;
+mod threads;
+mod tune;
+mod testing;
+
+#[cfg(test)]
+mod differential;
+
+use access::{Access, DefaultAccess};
+pub use basecase::{mul_basecase, sqr_basecase};
+pub use toom::{Workspace, Thresholds};
+pub use fft::Fft;
+pub use dwt::{Dwt, DwtResidue};
+pub use ntt::Ntt;
+pub use ssa::Ssa;
+pub use threads::{set_threads, threads};
+pub use tune::{tune, thresholds_file, BUILD_ID};
+
+// r = a * b with a and b both n limbs and r at least 2n.
+pub type MulKernel = fn(&mut [u64], &[u64], &[u64]);
+// r = a * a with a n limbs and r at least 2n.
+pub type SqrKernel = fn(&mut [u64], &[u64]);
+
 include!(concat!(env!("OUT_DIR"), "/modules.rs"));
+
+pub fn mul_kernel(n: usize) -> Option<MulKernel> {
+    mul_kernel_with::<DefaultAccess>(n)
+}
+
+pub fn sqr_kernel(n: usize) -> Option<SqrKernel> {
+    sqr_kernel_with::<DefaultAccess>(n)
+}
+
+// out = a * b in the low a.len() + b.len() limbs of out. This sets up a
+// Workspace each time, keep one around to multiply repeatedly.
+pub fn mul(out: &mut [u64], a: &[u64], b: &[u64]) {
+    Workspace::new().mul(out, a, b);
+}
+
+// out = a * a in the low 2 * a.len() limbs of out.
+pub fn sqr(out: &mut [u64], a: &[u64]) {
+    Workspace::new().sqr(out, a);
+}
@@ -0,0 +1,194 @@
+#![warn(rust_2018_idioms)]
+
+// Linear-time helpers on limb slices for Karatsuba and Toom-Cook, and a
+// sign-magnitude number over a fixed slice for the Toom interpolations,
+// which go negative along the way. Shorter operands are read as if zero
+// extended. Adding, subtracting and multiplying by a limb go to the AVX2
+// kernels in simd.rs where they can.
+
+use std::cmp::Ordering;
+
+use crate::simd;
+
+// out += b, out at least as long as b. Returns the carry out of out.
+pub(crate) fn add_to(out: &mut [u64], b: &[u64]) -> bool {
+    match simd::add_to(out, b) {
+        Some(carry) => carry,
+        None => add_to_scalar(out, b),
+    }
+}
+
+pub(crate) fn add_to_scalar(out: &mut [u64], b: &[u64]) -> bool {
+    let (lo, hi) = out.split_at_mut(b.len());
+    let mut carry = false;
+    for (x, &y) in lo.iter_mut().zip(b.iter()) {
+        let (s, c1) = x.overflowing_add(y);
+        let (s, c2) = s.overflowing_add(carry as u64);
+        *x = s;
+        carry = c1 || c2;
+    }
+    for x in hi.iter_mut() {
+        if !carry {
+            break;
+        }
+        let (s, c) = x.overflowing_add(1);
+        *x = s;
+        carry = c;
+    }
+    return carry;
+}
+
+// out -= b, out at least as long as b. Returns the borrow out of out.
+pub(crate) fn sub_from(out: &mut [u64], b: &[u64]) -> bool {
+    match simd::sub_from(out, b) {
+        Some(borrow) => borrow,
+        None => sub_from_scalar(out, b),
+    }
+}
+
+pub(crate) fn sub_from_scalar(out: &mut [u64], b: &[u64]) -> bool {
+    let (lo, hi) = out.split_at_mut(b.len());
+    let mut borrow = false;
+    for (x, &y) in lo.iter_mut().zip(b.iter()) {
+        let (s, b1) = x.overflowing_sub(y);
+        let (s, b2) = s.overflowing_sub(borrow as u64);
+        *x = s;
+        borrow = b1 || b2;
+    }
+    for x in hi.iter_mut() {
+        if !borrow {
+            break;
+        }
+        let (s, b) = x.overflowing_sub(1);
+        *x = s;
+        borrow = b;
+    }
+    return borrow;
+}
+
+// out = b - out, out at least as long as b.
+fn rsub_from(out: &mut [u64], b: &[u64]) -> bool {
+    let mut borrow = false;
+    for (i, x) in out.iter_mut().enumerate() {
+        let y = b.get(i).cloned().unwrap_or(0);
+        let (s, b1) = y.overflowing_sub(*x);
+        let (s, b2) = s.overflowing_sub(borrow as u64);
+        *x = s;
+        borrow = b1 || b2;
+    }
+    return borrow;
+}
+
+pub(crate) fn normalized_len(a: &[u64]) -> usize {
+    a.iter().rposition(|&x| x != 0).map_or(0, |i| i + 1)
+}
+
+pub(crate) fn cmp(a: &[u64], b: &[u64]) -> Ordering {
+    let (a, b) = (&a[..normalized_len(a)], &b[..normalized_len(b)]);
+    if a.len() != b.len() {
+        return a.len().cmp(&b.len());
+    }
+    return a.iter().rev().cmp(b.iter().rev());
+}
+
+// out = src, zero extended.
+pub(crate) fn copy_from(out: &mut [u64], src: &[u64]) {
+    let (lo, hi) = out.split_at_mut(src.len());
+    lo.copy_from_slice(src);
+    for x in hi.iter_mut() {
+        *x = 0;
+    }
+}
+
+// out *= m, returning the limb carried out of the top.
+pub(crate) fn mul_small(out: &mut [u64], m: u64) -> u64 {
+    match simd::mul_small(out, m) {
+        Some(carry) => carry,
+        None => mul_small_scalar(out, m),
+    }
+}
+
+pub(crate) fn mul_small_scalar(out: &mut [u64], m: u64) -> u64 {
+    let mut carry = 0;
+    for x in out.iter_mut() {
+        let t = (*x as u128) * (m as u128) + carry as u128;
+        *x = t as u64;
+        carry = (t >> 64) as u64;
+    }
+    return carry;
+}
+
+// out /= d, returning the remainder.
+pub(crate) fn divrem_small(out: &mut [u64], d: u64) -> u64 {
+    let mut r: u64 = 0;
+    for x in out.iter_mut().rev() {
+        let t = ((r as u128) << 64) | *x as u128;
+        *x = (t / d as u128) as u64;
+        r = (t % d as u128) as u64;
+    }
+    return r;
+}
+
+// out /= d, which has to divide it.
+fn div_exact_small(out: &mut [u64], d: u64) {
+    let r = divrem_small(out, d);
+    debug_assert_eq!(r, 0, "inexact division by {}", d);
+}
+
+// A signed number in sign-magnitude form over a fixed slice, which has to
+// be long enough for every value it takes.
+pub(crate) struct Signed<'a> {
+    pub(crate) neg: bool,
+    pub(crate) v: &'a mut [u64],
+}
+
+impl<'a> Signed<'a> {
+    pub(crate) fn new(v: &'a mut [u64]) -> Self {
+        Signed {neg: false, v}
+    }
+
+    pub(crate) fn set(&mut self, neg: bool, y: &[u64]) {
+        copy_from(self.v, y);
+        self.neg = neg;
+    }
+
+    // self += (-1)^neg * y
+    pub(crate) fn add(&mut self, neg: bool, y: &[u64]) {
+        if self.neg == neg {
+            let carry = add_to(self.v, y);
+            debug_assert!(!carry);
+        } else if cmp(self.v, y) != Ordering::Less {
+            sub_from(self.v, y);
+        } else {
+            rsub_from(self.v, y);
+            self.neg = neg;
+        }
+        if normalized_len(self.v) == 0 {
+            self.neg = false;
+        }
+    }
+
+    pub(crate) fn add_signed(&mut self, y: &Signed<'_>) {
+        self.add(y.neg, y.v);
+    }
+
+    pub(crate) fn sub_signed(&mut self, y: &Signed<'_>) {
+        self.add(!y.neg, y.v);
+    }
+
+    // self = y * m
+    pub(crate) fn set_mul(&mut self, y: &Signed<'_>, m: u64) {
+        self.set(y.neg, y.v);
+        let carry = mul_small(self.v, m);
+        debug_assert_eq!(carry, 0);
+    }
+
+    pub(crate) fn mul_small(&mut self, m: u64) {
+        let carry = mul_small(self.v, m);
+        debug_assert_eq!(carry, 0);
+    }
+
+    pub(crate) fn div_exact(&mut self, d: u64) {
+        div_exact_small(self.v, d);
+    }
+}
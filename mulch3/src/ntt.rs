@@ -0,0 +1,348 @@
+#![warn(rust_2018_idioms)]
+
+// Multiplication by number-theoretic transform, exact where Fft can only
+// check its round-off. The limbs themselves are the coefficients, and the
+// convolution is done modulo three primes below 2^62 that have 2^55-th
+// roots of unity. A coefficient is a sum of at most 2^55 products of two
+// limbs, under 2^183, which is less than the product of the primes, so
+// the Chinese Remainder Theorem gives it back exactly.
+//
+// The arithmetic modulo each prime is Montgomery's with R = 2^64, and
+// residues stay in that form from the inputs to the inverse transform.
+
+use rayon::prelude::*;
+use crate::limbs::add_to;
+use crate::threads;
+
+// p, a primitive root modulo p
+const PRIMES: [(u64, u64); 3] = [
+    (29 << 57 | 1, 3),
+    (69 << 55 | 1, 5),
+    (27 << 56 | 1, 5),
+];
+
+// The longest transform every prime has roots for.
+const MAX_LOG_LEN: u32 = 55;
+
+#[derive(Clone, Copy, Debug)]
+struct Prime {
+    p: u64,
+    // -1/p mod 2^64
+    neg_inv: u64,
+    // R^2 mod p
+    r2: u64,
+}
+
+impl Prime {
+    fn new(p: u64) -> Self {
+        // Newton's iteration for 1/p mod 2^64, each step doubles the bits.
+        let mut inv: u64 = 1;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(p.wrapping_mul(inv)));
+        }
+        debug_assert_eq!(p.wrapping_mul(inv), 1);
+        let r = ((1u128 << 64) % p as u128) as u64;
+        let r2 = (r as u128 * r as u128 % p as u128) as u64;
+        Prime {p, neg_inv: inv.wrapping_neg(), r2}
+    }
+
+    // a b / R mod p
+    fn mul(self, a: u64, b: u64) -> u64 {
+        let t = a as u128 * b as u128;
+        let m = (t as u64).wrapping_mul(self.neg_inv);
+        let u = ((t + m as u128 * self.p as u128) >> 64) as u64;
+        if u >= self.p {
+            return u - self.p;
+        }
+        return u;
+    }
+
+    fn add(self, a: u64, b: u64) -> u64 {
+        let s = a + b;
+        if s >= self.p {
+            return s - self.p;
+        }
+        return s;
+    }
+
+    fn sub(self, a: u64, b: u64) -> u64 {
+        if a >= b {
+            return a - b;
+        }
+        return a + self.p - b;
+    }
+
+    fn to_form(self, a: u64) -> u64 {
+        self.mul(a % self.p, self.r2)
+    }
+
+    fn out_of_form(self, a: u64) -> u64 {
+        self.mul(a, 1)
+    }
+
+    // a^e, a and the result in Montgomery form
+    fn pow(self, a: u64, mut e: u64) -> u64 {
+        let (mut r, mut b) = (self.to_form(1), a);
+        while e > 0 {
+            if e & 1 == 1 {
+                r = self.mul(r, b);
+            }
+            b = self.mul(b, b);
+            e >>= 1;
+        }
+        return r;
+    }
+
+    // b^-1 for b in standard form, as a standard form number.
+    fn inverse(self, b: u64) -> u64 {
+        let x = self.pow(self.to_form(b), self.p - 2);
+        return self.out_of_form(x);
+    }
+}
+
+// The roots for one prime and one length: w^j and w^-j for j below half
+// the length, w a primitive len-th root, and 1/len, all Montgomery.
+struct Roots {
+    prime: Prime,
+    fwd: Vec<u64>,
+    inv: Vec<u64>,
+    scale: u64,
+}
+
+impl Roots {
+    fn new(prime: Prime, g: u64, len: usize) -> Self {
+        let w = prime.pow(prime.to_form(g), (prime.p - 1) / len as u64);
+        let w_inv = prime.pow(w, len as u64 - 1);
+        let powers = |w: u64| {
+            let mut v = Vec::with_capacity(len / 2);
+            let mut x = prime.to_form(1);
+            for _ in 0..len / 2 {
+                v.push(x);
+                x = prime.mul(x, w);
+            }
+            v
+        };
+        let scale = prime.to_form(prime.inverse(len as u64 % prime.p));
+        Roots {prime, fwd: powers(w), inv: powers(w_inv), scale}
+    }
+
+    // Decimation in frequency, leaving x in bit-reversed order.
+    fn forward(&self, x: &mut [u64]) {
+        let (n, p) = (x.len(), self.prime);
+        let par = threads::parallel_len(n);
+        let mut len = n;
+        while len >= 2 {
+            let stride = n / len;
+            threads::chunks(x, len, par, |block| threads::halves(block, par, |j0, [lo, hi]| {
+                for (j, (u, v)) in lo.iter_mut().zip(hi.iter_mut()).enumerate() {
+                    let (a, b) = (*u, *v);
+                    *u = p.add(a, b);
+                    *v = p.mul(p.sub(a, b), self.fwd[(j0 + j) * stride]);
+                }
+            }));
+            len /= 2;
+        }
+    }
+
+    // Decimation in time from bit-reversed order, forward undone a
+    // butterfly at a time, and divided by the length.
+    fn inverse(&self, x: &mut [u64]) {
+        let (n, p) = (x.len(), self.prime);
+        let par = threads::parallel_len(n);
+        let mut len = 2;
+        while len <= n {
+            let stride = n / len;
+            threads::chunks(x, len, par, |block| threads::halves(block, par, |j0, [lo, hi]| {
+                for (j, (u, v)) in lo.iter_mut().zip(hi.iter_mut()).enumerate() {
+                    let (a, b) = (*u, p.mul(*v, self.inv[(j0 + j) * stride]));
+                    *u = p.add(a, b);
+                    *v = p.sub(a, b);
+                }
+            }));
+            len *= 2;
+        }
+        threads::chunks(x, threads::GRAIN, par, |chunk| {
+            for a in chunk.iter_mut() {
+                *a = p.mul(*a, self.scale);
+            }
+        });
+    }
+}
+
+pub struct Ntt {
+    primes: [Prime; 3],
+    roots: Vec<Roots>,
+    // the convolution modulo each prime, and the other operand's transforms
+    x: [Vec<u64>; 3],
+    y: [Vec<u64>; 3],
+    // 1/p0 mod p1, 1/(p0 p1) mod p2
+    crt: (u64, u64),
+}
+
+impl Default for Ntt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ntt {
+    pub fn new() -> Self {
+        let primes = [Prime::new(PRIMES[0].0), Prime::new(PRIMES[1].0), Prime::new(PRIMES[2].0)];
+        let (p0, p1, p2) = (primes[0].p, primes[1].p, primes[2].p);
+        let p01 = (p0 as u128 * p1 as u128 % p2 as u128) as u64;
+        Ntt {
+            primes,
+            roots: Vec::new(),
+            x: [Vec::new(), Vec::new(), Vec::new()],
+            y: [Vec::new(), Vec::new(), Vec::new()],
+            crt: (primes[1].inverse(p0 % p1), primes[2].inverse(p01)),
+        }
+    }
+
+    // The length of the last transform.
+    pub fn transform_len(&self) -> usize {
+        self.x[0].len()
+    }
+
+    // out = a * b in the low a.len() + b.len() limbs of out.
+    pub fn mul(&mut self, out: &mut [u64], a: &[u64], b: &[u64]) {
+        assert!(out.len() >= a.len() + b.len());
+        self.product(&mut out[..a.len() + b.len()], a, Some(b));
+    }
+
+    // out = a * a in the low 2 * a.len() limbs of out.
+    pub fn sqr(&mut self, out: &mut [u64], a: &[u64]) {
+        assert!(out.len() >= 2 * a.len());
+        self.product(&mut out[..2 * a.len()], a, None);
+    }
+
+    fn product(&mut self, out: &mut [u64], a: &[u64], b: Option<&[u64]>) {
+        let b_len = b.map_or(a.len(), |b| b.len());
+        for o in out.iter_mut() {
+            *o = 0;
+        }
+        if a.is_empty() || b_len == 0 {
+            return;
+        }
+        let len = (a.len() + b_len - 1).next_power_of_two();
+        assert!(len.trailing_zeros() <= MAX_LOG_LEN, "too long for the NTT primes");
+        if self.transform_len() != len {
+            self.roots = self.primes.iter().zip(PRIMES.iter())
+                .map(|(&prime, &(_, g))| Roots::new(prime, g, len))
+                .collect();
+            for v in self.x.iter_mut().chain(self.y.iter_mut()) {
+                v.resize(len, 0);
+            }
+        }
+        // The primes are independent of each other.
+        let one = |((x, y), roots): ((&mut Vec<u64>, &mut Vec<u64>), &Roots)| {
+            let p = roots.prime;
+            load(x, a, p);
+            roots.forward(x);
+            match b {
+                Some(b) => {
+                    load(y, b, p);
+                    roots.forward(y);
+                    for (u, &v) in x.iter_mut().zip(y.iter()) {
+                        *u = p.mul(*u, v);
+                    }
+                }
+                None => {
+                    for u in x.iter_mut() {
+                        *u = p.mul(*u, *u);
+                    }
+                }
+            }
+            roots.inverse(x);
+        };
+        if threads::parallel_len(len) {
+            self.x.par_iter_mut().zip(self.y.par_iter_mut()).zip(self.roots.par_iter()).for_each(one);
+        } else {
+            self.x.iter_mut().zip(self.y.iter_mut()).zip(self.roots.iter()).for_each(one);
+        }
+        self.reconstruct(out, a.len() + b_len - 1);
+    }
+
+    // Garner's CRT on each of the first count coefficients, added into out
+    // at its limb.
+    fn reconstruct(&self, out: &mut [u64], count: usize) {
+        let [q0, q1, q2] = self.primes;
+        let (inv0, inv01) = (q1.to_form(self.crt.0), q2.to_form(self.crt.1));
+        let p01 = q0.p as u128 * q1.p as u128;
+        for i in 0..count {
+            let r0 = q0.out_of_form(self.x[0][i]);
+            let r1 = q1.out_of_form(self.x[1][i]);
+            let r2 = q2.out_of_form(self.x[2][i]);
+            // x01 = r0 + p0 ((r1 - r0) / p0 mod p1), below p0 p1
+            let t = q1.mul(q1.sub(r1, r0 % q1.p), inv0);
+            let x01 = r0 as u128 + q0.p as u128 * t as u128;
+            // x = x01 + p0 p1 ((r2 - x01) / (p0 p1) mod p2)
+            let x01_mod = (x01 % q2.p as u128) as u64;
+            let t = q2.mul(q2.sub(r2, x01_mod), inv01) as u128;
+            // x01 + p01 t, with p01 t in 64-bit halves
+            let lo = (p01 as u64 as u128) * t;
+            let hi = (p01 >> 64) * t;
+            let w0 = (lo as u64 as u128) + (x01 as u64 as u128);
+            let w1 = (lo >> 64) + (hi as u64 as u128) + (x01 >> 64) + (w0 >> 64);
+            let w2 = (hi >> 64) + (w1 >> 64);
+            let v = [w0 as u64, w1 as u64, w2 as u64];
+            let end = (out.len() - i).min(3);
+            let carry = add_to(&mut out[i..], &v[..end]);
+            debug_assert!(!carry && v[end..].iter().all(|&w| w == 0));
+        }
+    }
+}
+
+// x = a mod p in Montgomery form, zero past the end of a.
+fn load(x: &mut [u64], a: &[u64], p: Prime) {
+    for (i, u) in x.iter_mut().enumerate() {
+        *u = match a.get(i) {
+            Some(&v) => p.to_form(v),
+            None => 0,
+        };
+    }
+}
+
+// tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft::Fft;
+    use crate::testing::{gmp_mul, limbs, SIZES};
+
+    #[test]
+    fn primes() {
+        for &(p, g) in PRIMES.iter() {
+            let q = Prime::new(p);
+            assert!(p < 1 << 62 && (p - 1).trailing_zeros() >= MAX_LOG_LEN);
+            // g^((p-1)/2) = -1 means g generates the 2-power part
+            let half = q.out_of_form(q.pow(q.to_form(g), (p - 1) / 2));
+            assert_eq!(half, p - 1);
+            assert_eq!(q.inverse(12345) as u128 * 12345 % p as u128, 1);
+        }
+    }
+
+    #[test]
+    fn matches_gmp_and_fft() {
+        let mut state = 0x1234_5678_9abc_def1;
+        let (mut ntt, mut fft) = (Ntt::new(), Fft::new());
+        for &(n, m) in SIZES.iter() {
+            let a = limbs(n, &mut state);
+            let b = limbs(m, &mut state);
+            let want = gmp_mul(&a, &b);
+            let mut out = vec![0u64; n + m];
+            ntt.mul(&mut out, &a, &b);
+            assert_eq!(out, want, "{} x {}", n, m);
+            fft.mul(&mut out, &a, &b);
+            assert_eq!(out, want, "fft {} x {}", n, m);
+            let mut sq = vec![0u64; 2 * n];
+            ntt.sqr(&mut sq, &a);
+            assert_eq!(sq, gmp_mul(&a, &a), "{}^2", n);
+        }
+        // r may be longer than the product, the rest is left alone.
+        let mut r = [7u64; 5];
+        ntt.mul(&mut r, &[3, 0], &[5, 1]);
+        assert_eq!(r, [15, 3, 0, 0, 7]);
+    }
+}
@@ -0,0 +1,342 @@
+#![warn(rust_2018_idioms)]
+
+// AVX2 versions of the linear-time limb helpers in limbs.rs: adding and
+// subtracting four limbs at a time, with the carries rippled across the
+// four as bit masks, running a carry or borrow up through a run of limbs
+// that pass it on, and multiplying by a single limb.
+//
+// The carries work like a carry-lookahead adder. A lane whose sum
+// wrapped generates a carry, a lane that's all ones after the add passes
+// one on, and adding the masks as small integers gives every lane that
+// takes one. Subtraction is the same with borrows and all zeros.
+//
+// They're gated on cfg(feature="nightly"), which build/main.rs sets on a
+// nightly or dev compiler like hazelprime-bootstrap's build.rs. With it,
+// whether they run is decided at runtime: on x86_64 with AVX2, and only
+// for slices long enough to be worth it. Otherwise limbs.rs does it in
+// scalar. The AVX2 kernels are always compiled on x86_64 so the tests can
+// hold them against the scalar ones.
+
+// Slices shorter than this stay scalar.
+const MIN_LIMBS: usize = 8;
+
+#[cfg(all(target_arch = "x86_64", feature = "nightly"))]
+pub(crate) fn enabled() -> bool {
+    is_x86_feature_detected!("avx2")
+}
+
+#[cfg(not(all(target_arch = "x86_64", feature = "nightly")))]
+pub(crate) fn enabled() -> bool {
+    false
+}
+
+// out += b as limbs::add_to, if it's for the AVX2 kernel.
+pub(crate) fn add_to(out: &mut [u64], b: &[u64]) -> Option<bool> {
+    if b.len() < MIN_LIMBS || !enabled() {
+        return None;
+    }
+    return Some(unsafe { avx2::add_to(out, b) });
+}
+
+// out -= b as limbs::sub_from.
+pub(crate) fn sub_from(out: &mut [u64], b: &[u64]) -> Option<bool> {
+    if b.len() < MIN_LIMBS || !enabled() {
+        return None;
+    }
+    return Some(unsafe { avx2::sub_from(out, b) });
+}
+
+// out *= m as limbs::mul_small.
+pub(crate) fn mul_small(out: &mut [u64], m: u64) -> Option<u64> {
+    if out.len() < MIN_LIMBS || !enabled() {
+        return None;
+    }
+    return Some(unsafe { avx2::mul_small(out, m) });
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    // Which lanes take a carry, and the carry out of the top, from the
+    // lanes that generate one, the lanes that pass one on, and the carry
+    // into the bottom. A lane can't do both.
+    #[inline]
+    fn ripple(generate: u32, propagate: u32, carry: bool) -> (u32, bool) {
+        let t = ((generate << 1) | carry as u32) + propagate;
+        return ((t ^ propagate) & 0xf, t >> 4 & 1 == 1);
+    }
+
+    // The lanes' top bits.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn mask(v: __m256i) -> u32 {
+        _mm256_movemask_pd(_mm256_castsi256_pd(v)) as u32
+    }
+
+    // All ones in the lanes whose bit is set.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn lanes(bits: u32) -> __m256i {
+        let lane = |i: u32| -((bits >> i & 1) as i64);
+        _mm256_set_epi64x(lane(3), lane(2), lane(1), lane(0))
+    }
+
+    // x < y unsigned, a lane at a time.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn less(x: __m256i, y: __m256i) -> __m256i {
+        let sign = _mm256_set1_epi64x(i64::MIN);
+        _mm256_cmpgt_epi64(_mm256_xor_si256(y, sign), _mm256_xor_si256(x, sign))
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn load(a: &[u64], i: usize) -> __m256i {
+        debug_assert!(i + 4 <= a.len());
+        _mm256_loadu_si256(a.as_ptr().add(i) as *const __m256i)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn store(a: &mut [u64], i: usize, v: __m256i) {
+        debug_assert!(i + 4 <= a.len());
+        _mm256_storeu_si256(a.as_mut_ptr().add(i) as *mut __m256i, v);
+    }
+
+    // Safety: AVX2 has to be there, as for everything below.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn add_to(out: &mut [u64], b: &[u64]) -> bool {
+        let n = b.len();
+        assert!(out.len() >= n);
+        let ones = _mm256_set1_epi64x(-1);
+        let mut carry = false;
+        let mut i = 0;
+        while i + 4 <= n {
+            let x = load(out, i);
+            let s = _mm256_add_epi64(x, load(b, i));
+            let (take, c) = ripple(mask(less(s, x)), mask(_mm256_cmpeq_epi64(s, ones)), carry);
+            store(out, i, _mm256_sub_epi64(s, lanes(take)));
+            carry = c;
+            i += 4;
+        }
+        for (x, &y) in out[i..n].iter_mut().zip(b[i..].iter()) {
+            let (s, c1) = x.overflowing_add(y);
+            let (s, c2) = s.overflowing_add(carry as u64);
+            *x = s;
+            carry = c1 || c2;
+        }
+        return carry && increment(&mut out[n..]);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn sub_from(out: &mut [u64], b: &[u64]) -> bool {
+        let n = b.len();
+        assert!(out.len() >= n);
+        let zero = _mm256_setzero_si256();
+        let mut borrow = false;
+        let mut i = 0;
+        while i + 4 <= n {
+            let (x, y) = (load(out, i), load(b, i));
+            let d = _mm256_sub_epi64(x, y);
+            let (take, c) = ripple(mask(less(x, y)), mask(_mm256_cmpeq_epi64(d, zero)), borrow);
+            store(out, i, _mm256_add_epi64(d, lanes(take)));
+            borrow = c;
+            i += 4;
+        }
+        for (x, &y) in out[i..n].iter_mut().zip(b[i..].iter()) {
+            let (s, b1) = x.overflowing_sub(y);
+            let (s, b2) = s.overflowing_sub(borrow as u64);
+            *x = s;
+            borrow = b1 || b2;
+        }
+        return borrow && decrement(&mut out[n..]);
+    }
+
+    // x += 1, returning the carry out. A carry runs on through all ones,
+    // which go four at a time.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn increment(x: &mut [u64]) -> bool {
+        let ones = _mm256_set1_epi64x(-1);
+        let mut i = 0;
+        while i + 4 <= x.len() && mask(_mm256_cmpeq_epi64(load(x, i), ones)) == 0xf {
+            store(x, i, _mm256_setzero_si256());
+            i += 4;
+        }
+        for y in x[i..].iter_mut() {
+            let (s, c) = y.overflowing_add(1);
+            *y = s;
+            if !c {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    // x -= 1, returning the borrow out, through zeros.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn decrement(x: &mut [u64]) -> bool {
+        let zero = _mm256_setzero_si256();
+        let mut i = 0;
+        while i + 4 <= x.len() && mask(_mm256_cmpeq_epi64(load(x, i), zero)) == 0xf {
+            store(x, i, _mm256_set1_epi64x(-1));
+            i += 4;
+        }
+        for y in x[i..].iter_mut() {
+            let (s, b) = y.overflowing_sub(1);
+            *y = s;
+            if !b {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    // Each limb times m from four 32 by 32 bit products, as a low and a
+    // high limb. The high limbs move up one and add to the low ones.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn mul_small(out: &mut [u64], m: u64) -> u64 {
+        let low = _mm256_set1_epi64x(0xffff_ffff);
+        let (ml, mh) = (_mm256_set1_epi64x(m as u32 as i64), _mm256_set1_epi64x((m >> 32) as i64));
+        let ones = _mm256_set1_epi64x(-1);
+        // the high limb of the product below, and the carry into this one
+        let (mut below, mut carry) = (0u64, false);
+        let mut i = 0;
+        while i + 4 <= out.len() {
+            let x = load(out, i);
+            let xh = _mm256_srli_epi64(x, 32);
+            let (p0, p1) = (_mm256_mul_epu32(x, ml), _mm256_mul_epu32(x, mh));
+            let (p2, p3) = (_mm256_mul_epu32(xh, ml), _mm256_mul_epu32(xh, mh));
+            let mid = _mm256_add_epi64(
+                _mm256_srli_epi64(p0, 32),
+                _mm256_add_epi64(_mm256_and_si256(p1, low), _mm256_and_si256(p2, low)),
+            );
+            let lo = _mm256_or_si256(_mm256_and_si256(p0, low), _mm256_slli_epi64(mid, 32));
+            let hi = _mm256_add_epi64(
+                _mm256_add_epi64(p3, _mm256_srli_epi64(p1, 32)),
+                _mm256_add_epi64(_mm256_srli_epi64(p2, 32), _mm256_srli_epi64(mid, 32)),
+            );
+            // hi3, hi0, hi1, hi2, then below in place of hi3
+            let up = _mm256_permute4x64_epi64(hi, 0b10_01_00_11);
+            let up = _mm256_blend_epi32(up, _mm256_set1_epi64x(below as i64), 0b11);
+            let s = _mm256_add_epi64(lo, up);
+            let (take, c) = ripple(mask(less(s, lo)), mask(_mm256_cmpeq_epi64(s, ones)), carry);
+            store(out, i, _mm256_sub_epi64(s, lanes(take)));
+            below = _mm256_extract_epi64(hi, 3) as u64;
+            carry = c;
+            i += 4;
+        }
+        // A high limb is at most m - 1, so this can't wrap.
+        let mut c = below + carry as u64;
+        for x in out[i..].iter_mut() {
+            let t = (*x as u128) * (m as u128) + c as u128;
+            *x = t as u64;
+            c = (t >> 64) as u64;
+        }
+        return c;
+    }
+}
+
+// Never called, enabled is false off x86_64.
+#[cfg(not(target_arch = "x86_64"))]
+mod avx2 {
+    pub(super) unsafe fn add_to(_: &mut [u64], _: &[u64]) -> bool {
+        unreachable!()
+    }
+
+    pub(super) unsafe fn sub_from(_: &mut [u64], _: &[u64]) -> bool {
+        unreachable!()
+    }
+
+    pub(super) unsafe fn mul_small(_: &mut [u64], _: u64) -> u64 {
+        unreachable!()
+    }
+}
+
+// tests
+
+#[cfg(all(test, target_arch = "x86_64"))]
+mod tests {
+    use super::avx2;
+    use crate::limbs::{add_to_scalar, sub_from_scalar, mul_small_scalar};
+    use crate::testing::next;
+
+    // Random limbs, but with runs of all ones and zeros for the carries
+    // to go through.
+    fn limbs(n: usize, state: &mut u64) -> Vec<u64> {
+        (0..n).map(|_| match next(state) % 4 {
+            0 => 0,
+            1 => u64::MAX,
+            _ => next(state),
+        }).collect()
+    }
+
+    fn avx2() -> bool {
+        if !is_x86_feature_detected!("avx2") {
+            eprintln!("no AVX2 here, nothing to check");
+            return false;
+        }
+        return true;
+    }
+
+    #[test]
+    fn add_and_sub_match_scalar() {
+        if !avx2() {
+            return;
+        }
+        let mut state = 0x51d2_07c3_e9a4_b186;
+        for n in 0..40 {
+            for extra in [0, 1, 5, 12].iter() {
+                for _ in 0..50 {
+                    let (a, b) = (limbs(n + extra, &mut state), limbs(n, &mut state));
+                    let (mut want, mut got) = (a.clone(), a.clone());
+                    let carry = add_to_scalar(&mut want, &b);
+                    assert_eq!(unsafe { avx2::add_to(&mut got, &b) }, carry);
+                    assert_eq!(got, want, "add {:?} {:?}", a, b);
+                    let (mut want, mut got) = (a.clone(), a.clone());
+                    let borrow = sub_from_scalar(&mut want, &b);
+                    assert_eq!(unsafe { avx2::sub_from(&mut got, &b) }, borrow);
+                    assert_eq!(got, want, "sub {:?} {:?}", a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn carries_all_the_way() {
+        if !avx2() {
+            return;
+        }
+        for n in 1..20 {
+            let mut x = vec![u64::MAX; n];
+            let mut one = vec![0u64; n];
+            one[0] = 1;
+            assert!(unsafe { avx2::add_to(&mut x, &one) });
+            assert_eq!(x, vec![0; n]);
+            assert!(unsafe { avx2::sub_from(&mut x, &one) });
+            assert_eq!(x, vec![u64::MAX; n]);
+            assert!(unsafe { avx2::increment(&mut x) });
+            assert!(unsafe { avx2::decrement(&mut x) });
+            assert_eq!(x, vec![u64::MAX; n]);
+        }
+    }
+
+    #[test]
+    fn mul_small_matches_scalar() {
+        if !avx2() {
+            return;
+        }
+        let mut state = 0x0c4f_3b8e_27d1_a965;
+        for n in 0..40 {
+            for _ in 0..50 {
+                let a = limbs(n, &mut state);
+                for &m in [0, 1, 2, 3, u32::MAX as u64, 1 << 32, u64::MAX, next(&mut state)].iter() {
+                    let (mut want, mut got) = (a.clone(), a.clone());
+                    let carry = mul_small_scalar(&mut want, m);
+                    assert_eq!(unsafe { avx2::mul_small(&mut got, m) }, carry);
+                    assert_eq!(got, want, "{:?} * {}", a, m);
+                }
+            }
+        }
+    }
+}
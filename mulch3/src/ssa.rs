@@ -0,0 +1,482 @@
+#![warn(rust_2018_idioms)]
+
+// Schönhage–Strassen multiplication modulo 2^N+1.
+//
+// With N = K M, a mod 2^N+1 is K pieces of M bits and the product is the
+// negacyclic convolution of the pieces, since 2^N = -1 wraps the high
+// half around negated. That convolution is done by FFT in the smaller
+// ring Z/(2^N'+1), N' a bit over 2M, where 2 is a root of unity: theta =
+// 2^(N'/K) has theta^K = -1, weighting the pieces with powers of theta
+// makes the convolution negacyclic, and omega = theta^2 is the K-th root
+// for the transform. Every multiplication by a root is a shift, and the K
+// pointwise products are modulo 2^N'+1 again, so they recurse until
+// they're small enough for the Workspace.
+//
+// Residues modulo 2^N+1 are N/64 + 1 limbs, from 0 to 2^N inclusive. The
+// transform wants N a multiple of 64, and K is kept to a power of two
+// dividing N/64 so the pieces are whole limbs. Any other N, like the
+// Fermat numbers below 2^64, gets the whole product and a fold at bit N.
+
+use crate::limbs::{add_to, sub_from, copy_from, normalized_len};
+use rayon::prelude::*;
+use crate::toom::Workspace;
+use crate::threads;
+
+// Residues below this many limbs are multiplied and folded directly.
+const THRESHOLD: usize = 256;
+
+// K for about N bits: the square root, so the pieces and their number
+// grow together.
+fn wanted_log_k(bits: usize) -> u32 {
+    let log = usize::BITS - bits.leading_zeros();
+    return log.div_ceil(2);
+}
+
+// The arithmetic modulo 2^(64 m)+1 on m+1 limb residues.
+#[derive(Clone, Copy)]
+struct Ring {
+    m: usize,
+}
+
+impl Ring {
+    fn bits(self) -> usize {
+        64 * self.m
+    }
+
+    // Brings x, m+1 limbs, from anything up to 2^64 times 2^N back to
+    // 0..=2^N, with hi 2^N = -hi.
+    fn norm(self, x: &mut [u64]) {
+        let hi = x[self.m];
+        x[self.m] = 0;
+        if sub_from(x, &[hi]) {
+            // x + 2^N + 1, with the borrow having wrapped the top limb
+            x[self.m] = x[self.m].wrapping_add(1);
+            add_to(x, &[1]);
+        }
+    }
+
+    // x += y
+    fn add(self, x: &mut [u64], y: &[u64]) {
+        add_to(x, y);
+        self.norm(x);
+    }
+
+    // x -= y
+    fn sub(self, x: &mut [u64], y: &[u64]) {
+        if sub_from(x, y) {
+            x[self.m] = x[self.m].wrapping_add(1);
+            add_to(x, &[1]);
+        }
+        self.norm(x);
+    }
+
+    // x = -x, as 2^N+1 - x: the complement is -x - 1 modulo the m+1
+    // limbs, and adding 2^N+2 to it wraps around to the answer.
+    fn neg(self, x: &mut [u64]) {
+        if normalized_len(x) == 0 {
+            return;
+        }
+        for w in x.iter_mut() {
+            *w = !*w;
+        }
+        add_to(x, &[2]);
+        x[self.m] = x[self.m].wrapping_add(1);
+    }
+
+    // x = x 2^s, s below 2N. buf is 2m+2 limbs.
+    fn mul_2exp(self, x: &mut [u64], s: usize, buf: &mut [u64]) {
+        let n = self.bits();
+        let (negate, s) = if s >= n { (true, s - n) } else { (false, s) };
+        let (limbs, bits) = (s / 64, (s % 64) as u32);
+        copy_from(buf, &[]);
+        for (i, &w) in x.iter().enumerate() {
+            buf[i + limbs] |= w << bits;
+            if bits != 0 {
+                buf[i + limbs + 1] |= w >> (64 - bits);
+            }
+        }
+        // lo - hi, where hi is below 2^N since x 2^s is below 2^(2N+1)
+        let (lo, hi) = buf.split_at(self.m);
+        copy_from(x, lo);
+        self.sub(x, &hi[..normalized_len(hi)]);
+        if negate {
+            self.neg(x);
+        }
+    }
+
+    // x mod 2^N+1 for x of any length: the N-bit chunks with alternating
+    // signs, since 2^N = -1.
+    fn fold(self, out: &mut [u64], x: &[u64]) {
+        copy_from(out, &[]);
+        for (i, c) in x.chunks(self.m).enumerate() {
+            if i % 2 == 0 {
+                self.add(out, c);
+            } else {
+                self.sub(out, c);
+            }
+        }
+    }
+}
+
+pub struct Ssa {
+    workspace: Workspace,
+    threshold: usize,
+}
+
+impl Default for Ssa {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ssa {
+    pub fn new() -> Self {
+        Self::with_threshold(THRESHOLD)
+    }
+
+    // Products modulo 2^N+1 with at least threshold limbs go through the
+    // transform, smaller ones are multiplied directly.
+    pub fn with_threshold(threshold: usize) -> Self {
+        Ssa {workspace: Workspace::new(), threshold}
+    }
+
+    // out = a * b mod 2^n+1, with a, b and out n/64 + 1 limbs and a and b
+    // at most 2^n.
+    pub fn mul_mod(&mut self, out: &mut [u64], a: &[u64], b: &[u64], n: usize) {
+        self.any_mod(out, a, Some(b), n);
+    }
+
+    // out = a * a mod 2^n+1, like mul_mod.
+    pub fn sqr_mod(&mut self, out: &mut [u64], a: &[u64], n: usize) {
+        self.any_mod(out, a, None, n);
+    }
+
+    // out = a * b in the low a.len() + b.len() limbs of out, modulo a
+    // 2^N+1 too big for it to wrap.
+    pub fn mul(&mut self, out: &mut [u64], a: &[u64], b: &[u64]) {
+        assert!(out.len() >= a.len() + b.len());
+        self.product(&mut out[..a.len() + b.len()], a, Some(b));
+    }
+
+    // out = a * a in the low 2 * a.len() limbs of out.
+    pub fn sqr(&mut self, out: &mut [u64], a: &[u64]) {
+        assert!(out.len() >= 2 * a.len());
+        self.product(&mut out[..2 * a.len()], a, None);
+    }
+
+    fn product(&mut self, out: &mut [u64], a: &[u64], b: Option<&[u64]>) {
+        // The smallest N past the product with as many twos in N/64 as
+        // the K for it wants.
+        let m = out.len() + 1;
+        let align = 1 << wanted_log_k(64 * m).saturating_sub(6);
+        let m = m.div_ceil(align) * align;
+        let mut x = vec![0u64; m + 1];
+        let mut r = vec![0u64; m + 1];
+        copy_from(&mut x, a);
+        match b {
+            Some(b) => {
+                let mut y = vec![0u64; m + 1];
+                copy_from(&mut y, b);
+                self.product_mod(&mut r, &x, Some(&y), 64 * m);
+            }
+            None => self.product_mod(&mut r, &x, None, 64 * m),
+        }
+        out.copy_from_slice(&r[..out.len()]);
+        debug_assert_eq!(normalized_len(&r[out.len()..]), 0);
+    }
+
+    // Products modulo 2^n+1 for n not a multiple of 64 are taken whole and
+    // folded: 2^n = -1 makes the bits from n up come off the ones below.
+    fn any_mod(&mut self, out: &mut [u64], a: &[u64], b: Option<&[u64]>, n: usize) {
+        if n.is_multiple_of(64) {
+            self.product_mod(out, a, b, n);
+            return;
+        }
+        let (limbs, bits) = (n / 64, (n % 64) as u32);
+        let w = limbs + 1;
+        let bb = b.unwrap_or(a);
+        assert!(a.len() == w && bb.len() == w && out.len() == w);
+        let residue = |x: &[u64]| x[limbs] >> bits == 0 || (x[limbs] == 1 << bits && normalized_len(&x[..limbs]) == 0);
+        assert!(residue(a) && residue(bb), "residues must be at most 2^n");
+        let mut p = vec![0u64; 2 * w];
+        match b {
+            Some(b) => self.mul(&mut p, a, b),
+            None => self.sqr(&mut p, a),
+        }
+        // p is at most 2^2n, so the high part is at most 2^n like out.
+        let mut hi = vec![0u64; w];
+        for (i, h) in hi.iter_mut().enumerate() {
+            *h = p[i + limbs] >> bits;
+            if i + limbs + 1 < p.len() {
+                *h |= p[i + limbs + 1] << (64 - bits);
+            }
+        }
+        copy_from(out, &p[..w]);
+        out[limbs] &= (1 << bits) - 1;
+        if sub_from(out, &hi) {
+            // wrapped around the limbs, and adding 2^n+1 wraps it back
+            add_to(out, &[1]);
+            out[limbs] = out[limbs].wrapping_add(1 << bits);
+        }
+    }
+
+    fn product_mod(&mut self, out: &mut [u64], a: &[u64], b: Option<&[u64]>, n: usize) {
+        assert!(n.is_multiple_of(64) && n > 0, "n must be a positive multiple of 64");
+        let ring = Ring {m: n / 64};
+        assert!(a.len() == ring.m + 1 && out.len() == ring.m + 1);
+        let bb = b.unwrap_or(a);
+        assert!(bb.len() == ring.m + 1);
+        assert!(a[ring.m] <= 1 && bb[ring.m] <= 1, "residues must be at most 2^n");
+        // 2^n is -1
+        if a[ring.m] == 1 {
+            out.copy_from_slice(bb);
+            ring.neg(out);
+            return;
+        }
+        if bb[ring.m] == 1 {
+            out.copy_from_slice(a);
+            ring.neg(out);
+            return;
+        }
+        let log_k = wanted_log_k(n).min(ring.m.trailing_zeros());
+        if ring.m < self.threshold || log_k < 2 {
+            let mut p = vec![0u64; 2 * ring.m];
+            match b {
+                Some(b) => self.workspace.mul(&mut p, &a[..ring.m], &b[..ring.m]),
+                None => self.workspace.sqr(&mut p, &a[..ring.m]),
+            }
+            ring.fold(out, &p);
+            return;
+        }
+        self.transform(out, a, b, ring, log_k);
+    }
+
+    fn transform(&mut self, out: &mut [u64], a: &[u64], b: Option<&[u64]>, ring: Ring, log_k: u32) {
+        let k = 1usize << log_k;
+        let piece = ring.m / k;
+        // N' past 2M + log K + 1 bits, a multiple of K for theta and of the
+        // alignment the next level down wants.
+        let need = 2 * 64 * piece + log_k as usize + 1;
+        let align = k.max(64 << wanted_log_k(need).saturating_sub(6));
+        let inner = Ring {m: need.div_ceil(align) * align / 64};
+        let (w, n2) = (inner.m + 1, inner.bits());
+        let theta = n2 / k;
+
+        let mut buf = vec![0u64; 2 * w];
+        let mut tmp = vec![0u64; w];
+        let load = |x: &mut Vec<u64>, a: &[u64], buf: &mut [u64]| {
+            for (i, c) in x.chunks_exact_mut(w).enumerate() {
+                copy_from(c, &a[i * piece..(i + 1) * piece]);
+                inner.mul_2exp(c, i * theta, buf);
+            }
+        };
+        let mut x = vec![0u64; k * w];
+        load(&mut x, a, &mut buf);
+        fft(&mut x, inner, k, 2 * theta, &mut tmp, &mut buf);
+        let y = b.map(|b| {
+            let mut y = vec![0u64; k * w];
+            load(&mut y, b, &mut buf);
+            fft(&mut y, inner, k, 2 * theta, &mut tmp, &mut buf);
+            y
+        });
+        // The pointwise products, on the pool with an Ssa for each task.
+        let threshold = self.threshold;
+        let pointwise = |ssa: &mut Ssa, p: &mut Vec<u64>, (i, u): (usize, &mut [u64])| {
+            let v = y.as_ref().map(|y| &y[i * w..(i + 1) * w]);
+            ssa.product_mod(p, u, v, n2);
+            u.copy_from_slice(p);
+        };
+        if threads::parallel_len(x.len()) {
+            x.par_chunks_mut(w).enumerate().for_each_init(
+                || (Ssa::with_threshold(threshold), vec![0u64; w]),
+                |(ssa, p), c| pointwise(ssa, p, c),
+            );
+        } else {
+            let mut p = vec![0u64; w];
+            for c in x.chunks_exact_mut(w).enumerate() {
+                pointwise(self, &mut p, c);
+            }
+        }
+        ifft(&mut x, inner, k, 2 * theta, &mut tmp, &mut buf);
+
+        // Undo the weights and the factor of K, then add the coefficients
+        // in at their pieces, the negative ones separately. A coefficient is
+        // a sum of at most K products of two pieces, so anything longer
+        // than that is 2^N'+1 minus one.
+        let len = ring.m + w + 1;
+        let (mut plus, mut minus) = (vec![0u64; len], vec![0u64; len]);
+        let max = 2 * 64 * piece + log_k as usize;
+        for (i, c) in x.chunks_exact_mut(w).enumerate() {
+            let s = (4 * n2 - log_k as usize - i * theta) % (2 * n2);
+            inner.mul_2exp(c, s, &mut buf);
+            if bit_len(c) > max {
+                inner.neg(c);
+                add_to(&mut minus[i * piece..], &c[..normalized_len(c)]);
+            } else {
+                add_to(&mut plus[i * piece..], &c[..normalized_len(c)]);
+            }
+        }
+        let mut neg = vec![0u64; ring.m + 1];
+        ring.fold(out, &plus);
+        ring.fold(&mut neg, &minus);
+        ring.sub(out, &neg);
+    }
+}
+
+fn bit_len(x: &[u64]) -> usize {
+    let len = normalized_len(x);
+    if len == 0 {
+        return 0;
+    }
+    return 64 * len - x[len - 1].leading_zeros() as usize;
+}
+
+// Decimation in frequency on the k residues in x, with omega = 2^step,
+// leaving them in bit-reversed order.
+fn fft(x: &mut [u64], ring: Ring, k: usize, step: usize, tmp: &mut [u64], buf: &mut [u64]) {
+    let mut len = k;
+    while len >= 2 {
+        let stride = k / len;
+        butterflies(x, ring, len, tmp, buf, |j, u, v, tmp, buf| {
+            tmp.copy_from_slice(u);
+            ring.add(u, v);
+            ring.sub(tmp, v);
+            v.copy_from_slice(tmp);
+            ring.mul_2exp(v, j * stride * step, buf);
+        });
+        len /= 2;
+    }
+}
+
+// fft undone a butterfly at a time, leaving k times the inverse.
+fn ifft(x: &mut [u64], ring: Ring, k: usize, step: usize, tmp: &mut [u64], buf: &mut [u64]) {
+    let n2 = 2 * ring.bits();
+    let mut len = 2;
+    while len <= k {
+        let stride = k / len;
+        butterflies(x, ring, len, tmp, buf, |j, u, v, tmp, buf| {
+            ring.mul_2exp(v, (n2 - j * stride * step) % n2, buf);
+            tmp.copy_from_slice(u);
+            ring.add(u, v);
+            ring.sub(tmp, v);
+            v.copy_from_slice(tmp);
+        });
+        len *= 2;
+    }
+}
+
+// f(j, u, v, tmp, buf) on the j-th pair of residues half a block apart,
+// for blocks of len residues. On the pool each task has a tmp and buf of
+// its own.
+fn butterflies<F>(x: &mut [u64], ring: Ring, len: usize, tmp: &mut [u64], buf: &mut [u64], f: F)
+    where F: Fn(usize, &mut [u64], &mut [u64], &mut [u64], &mut [u64]) + Sync + Send
+{
+    let w = ring.m + 1;
+    let half = len / 2;
+    if threads::parallel_len(x.len()) {
+        x.par_chunks_mut(len * w).for_each(|block| {
+            let (lo, hi) = block.split_at_mut(half * w);
+            lo.par_chunks_mut(w).zip(hi.par_chunks_mut(w)).enumerate().for_each_init(
+                || (vec![0u64; w], vec![0u64; 2 * w]),
+                |(tmp, buf), (j, (u, v))| f(j, u, v, tmp, buf),
+            );
+        });
+    } else {
+        for block in x.chunks_exact_mut(len * w) {
+            let (lo, hi) = block.split_at_mut(half * w);
+            for (j, (u, v)) in lo.chunks_exact_mut(w).zip(hi.chunks_exact_mut(w)).enumerate() {
+                f(j, u, v, tmp, buf);
+            }
+        }
+    }
+}
+
+// tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{gmp_mul, gmp_rem, next};
+
+    // a * b mod 2^n+1 by GMP.
+    fn gmp_mul_mod(a: &[u64], b: &[u64], n: usize) -> Vec<u64> {
+        let mut f = vec![0u64; n / 64 + 1];
+        f[0] = 1;
+        f[n / 64] |= 1 << (n % 64);
+        return gmp_rem(&gmp_mul(a, b), &f);
+    }
+
+    // A residue mod 2^n+1: random, all ones, or 2^n.
+    fn residue(n: usize, state: &mut u64) -> Vec<u64> {
+        let mut x = vec![0u64; n / 64 + 1];
+        let top = (1 << (n % 64)) - 1;
+        match next(state) % 8 {
+            0 => x[n / 64] = 1 << (n % 64),
+            1 => {
+                for w in x[..n / 64].iter_mut() {
+                    *w = u64::MAX;
+                }
+                x[n / 64] = top;
+            }
+            _ => {
+                for w in x.iter_mut() {
+                    *w = next(state);
+                }
+                x[n / 64] &= top;
+            }
+        }
+        return x;
+    }
+
+    #[test]
+    fn mul_mod_matches_gmp() {
+        let mut state = 0xdead_beef_1234_5678;
+        for &threshold in [4, 16, THRESHOLD].iter() {
+            let mut ssa = Ssa::with_threshold(threshold);
+            for &n in [64, 128, 64 * 12, 1024, 64 * 48, 4096, 64 * 1000, 1 << 16].iter() {
+                for _ in 0..4 {
+                    let a = residue(n, &mut state);
+                    let b = residue(n, &mut state);
+                    let mut out = vec![0u64; n / 64 + 1];
+                    ssa.mul_mod(&mut out, &a, &b, n);
+                    assert_eq!(out, gmp_mul_mod(&a, &b, n), "n {} threshold {}", n, threshold);
+                    ssa.sqr_mod(&mut out, &a, n);
+                    assert_eq!(out, gmp_mul_mod(&a, &a, n), "n {}^2 threshold {}", n, threshold);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mul_mod_any_n() {
+        let mut state = 0x1234;
+        let mut ssa = Ssa::with_threshold(8);
+        for &n in [1, 2, 16, 32, 63, 65, 100, 1000, 64 * 48 + 5].iter() {
+            for _ in 0..8 {
+                let a = residue(n, &mut state);
+                let b = residue(n, &mut state);
+                let mut out = vec![0u64; n / 64 + 1];
+                ssa.mul_mod(&mut out, &a, &b, n);
+                assert_eq!(out, gmp_mul_mod(&a, &b, n), "n {}", n);
+                ssa.sqr_mod(&mut out, &a, n);
+                assert_eq!(out, gmp_mul_mod(&a, &a, n), "n {}^2", n);
+            }
+        }
+    }
+
+    #[test]
+    fn mul_matches_gmp() {
+        let mut state = 99;
+        let mut ssa = Ssa::with_threshold(8);
+        for &(n, m) in [(1, 1), (3, 2), (100, 100), (1000, 333), (4096, 4096)].iter() {
+            let a: Vec<u64> = (0..n).map(|_| next(&mut state)).collect();
+            let b: Vec<u64> = (0..m).map(|_| next(&mut state)).collect();
+            let mut out = vec![0u64; n + m];
+            ssa.mul(&mut out, &a, &b);
+            assert_eq!(out, gmp_mul(&a, &b), "{} x {}", n, m);
+            let mut sq = vec![0u64; 2 * n];
+            ssa.sqr(&mut sq, &a);
+            assert_eq!(sq, gmp_mul(&a, &a), "{}^2", n);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+#![warn(rust_2018_idioms)]
+
+// What the tests share: GMP's answers to check against, and inputs from
+// a seeded generator so a failure can be replayed. The tuner times
+// squarings of the same generator's limbs.
+
+#[cfg(test)]
+use gmp_mpfr_sys::gmp;
+
+#[cfg(test)]
+use crate::limbs::normalized_len;
+
+// a * b by GMP, in a.len() + b.len() limbs.
+#[cfg(test)]
+pub(crate) fn gmp_mul(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut r = vec![0u64; a.len() + b.len()];
+    unsafe {
+        gmp::mpn_mul(r.as_mut_ptr(), a.as_ptr(), a.len() as gmp::size_t, b.as_ptr(), b.len() as gmp::size_t);
+    }
+    return r;
+}
+
+#[cfg(test)]
+pub(crate) fn gmp_sqr(a: &[u64]) -> Vec<u64> {
+    let mut r = vec![0u64; 2 * a.len()];
+    unsafe {
+        gmp::mpn_sqr(r.as_mut_ptr(), a.as_ptr(), a.len() as gmp::size_t);
+    }
+    return r;
+}
+
+// x mod modulus by GMP, as long as modulus.
+#[cfg(test)]
+pub(crate) fn gmp_rem(x: &[u64], modulus: &[u64]) -> Vec<u64> {
+    let m = normalized_len(modulus);
+    let mut x = x.to_vec();
+    if x.len() < m {
+        x.resize(m, 0);
+    }
+    let mut q = vec![0u64; x.len() - m + 1];
+    let mut r = vec![0u64; modulus.len()];
+    unsafe {
+        gmp::mpn_tdiv_qr(q.as_mut_ptr(), r.as_mut_ptr(), 0, x.as_ptr(), x.len() as gmp::size_t, modulus.as_ptr(), m as gmp::size_t);
+    }
+    return r;
+}
+
+// xorshift64, which any nonzero seed starts.
+pub(crate) fn next(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    return *state;
+}
+
+// n random limbs, or one time in four all ones for the most carries.
+#[cfg(test)]
+pub(crate) fn limbs(n: usize, state: &mut u64) -> Vec<u64> {
+    match next(state) % 4 {
+        0 => vec![u64::MAX; n],
+        _ => (0..n).map(|_| next(state)).collect(),
+    }
+}
+
+// Lengths the transforms are checked at, from one limb to long ones and
+// very unequal ones.
+#[cfg(test)]
+pub(crate) const SIZES: [(usize, usize); 8] = [
+    (1, 1), (2, 1), (5, 5), (17, 3), (100, 100), (1000, 999), (4096, 4096), (20000, 5),
+];
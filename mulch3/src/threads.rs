@@ -0,0 +1,150 @@
+#![warn(rust_2018_idioms)]
+
+// The big multiplications hand their independent work to rayon's global
+// pool: Toom's sub-products, the passes of the transforms, the NTT's
+// primes and SSA's pointwise products. Only big enough work is handed
+// out, below that it runs in order on the calling thread.
+//
+// Splitting the work never changes what's computed, each butterfly or
+// sub-product is done the same way whichever thread does it, so results
+// are the same for any number of threads, floating-point round-off
+// included.
+
+use rayon::prelude::*;
+
+// Butterflies in one task.
+pub(crate) const GRAIN: usize = 1 << 10;
+
+// Sub-product sizes, in limbs, from which Toom's go on the pool.
+pub(crate) const PARALLEL_LIMBS: usize = 1 << 10;
+
+// Transform lengths from which the passes go on the pool.
+pub(crate) const PARALLEL_LEN: usize = 1 << 13;
+
+// Sets up the global pool with n threads. Has to come before anything
+// else uses it.
+pub fn set_threads(n: usize) -> Result<(), String> {
+    assert!(n >= 1, "need at least one thread");
+    return rayon::ThreadPoolBuilder::new()
+        .num_threads(n)
+        .build_global()
+        .map_err(|e| e.to_string());
+}
+
+// The threads in the pool the current call runs on.
+pub fn threads() -> usize {
+    rayon::current_num_threads()
+}
+
+pub(crate) fn parallel() -> bool {
+    threads() > 1
+}
+
+// Whether a transform of length len goes on the pool.
+pub(crate) fn parallel_len(len: usize) -> bool {
+    return len >= PARALLEL_LEN && parallel();
+}
+
+// f on each len-long chunk of x, on the pool if par.
+pub(crate) fn chunks<T, F>(x: &mut [T], len: usize, par: bool, f: F)
+    where T: Send, F: Fn(&mut [T]) + Sync + Send
+{
+    if par {
+        x.par_chunks_mut(len).for_each(f);
+    } else {
+        x.chunks_mut(len).for_each(f);
+    }
+}
+
+// f on the halves of block side by side, as f(j0, [h0, h1]) with j0
+// where they start in their half. On the pool if par, GRAIN at a time.
+pub(crate) fn halves<T, F>(block: &mut [T], par: bool, f: F)
+    where T: Send, F: Fn(usize, [&mut [T]; 2]) + Sync + Send
+{
+    let h = block.len() / 2;
+    let (h0, h1) = block.split_at_mut(h);
+    if par && h > GRAIN {
+        (h0.par_chunks_mut(GRAIN), h1.par_chunks_mut(GRAIN))
+            .into_par_iter()
+            .enumerate()
+            .for_each(|(i, (a, b))| f(i * GRAIN, [a, b]));
+    } else {
+        f(0, [h0, h1]);
+    }
+}
+
+// halves for quarters.
+pub(crate) fn quarters<T, F>(block: &mut [T], par: bool, f: F)
+    where T: Send, F: Fn(usize, [&mut [T]; 4]) + Sync + Send
+{
+    let q = block.len() / 4;
+    let (q0, rest) = block.split_at_mut(q);
+    let (q1, rest) = rest.split_at_mut(q);
+    let (q2, q3) = rest.split_at_mut(q);
+    if par && q > GRAIN {
+        (q0.par_chunks_mut(GRAIN), q1.par_chunks_mut(GRAIN), q2.par_chunks_mut(GRAIN), q3.par_chunks_mut(GRAIN))
+            .into_par_iter()
+            .enumerate()
+            .for_each(|(i, (a, b, c, d))| f(i * GRAIN, [a, b, c, d]));
+    } else {
+        f(0, [q0, q1, q2, q3]);
+    }
+}
+
+// tests
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::testing::{gmp_mul, limbs};
+
+    // f on pools of 1, 2 and 4 threads, which should all agree.
+    fn on_pools<T: PartialEq + std::fmt::Debug + Send>(f: impl Fn() -> T + Sync) -> T {
+        let run = |n| rayon::ThreadPoolBuilder::new().num_threads(n).build().unwrap().install(&f);
+        let want = run(1);
+        for &n in [2, 4].iter() {
+            assert_eq!(run(n), want, "{} threads", n);
+        }
+        return want;
+    }
+
+    fn product(len: usize, f: impl FnOnce(&mut [u64])) -> Vec<u64> {
+        let mut r = vec![0u64; len];
+        f(&mut r);
+        return r;
+    }
+
+    #[test]
+    fn same_for_any_number_of_threads() {
+        let mut state = 0x7e5d_1c0f_42a8_93b6;
+        let (a, b) = (limbs(5000, &mut state), limbs(4100, &mut state));
+        let (want, want_sqr) = (gmp_mul(&a, &b), gmp_mul(&a, &a));
+
+        let toom = Thresholds {fft: usize::MAX, ..Thresholds::default()};
+        let karatsuba = Thresholds {karatsuba: 24, toom3: usize::MAX, toom4: usize::MAX, fft: usize::MAX};
+        for &t in [toom, karatsuba].iter() {
+            let r = on_pools(|| product(9100, |r| Workspace::with_thresholds(t).mul(r, &a, &b)));
+            assert_eq!(r, want, "{:?}", t);
+            let r = on_pools(|| product(10000, |r| Workspace::with_thresholds(t).sqr(r, &a)));
+            assert_eq!(r, want_sqr, "{:?}", t);
+        }
+        // The round-off too, to the bit.
+        let (r, _) = on_pools(|| {
+            let mut fft = Fft::new();
+            (product(9100, |r| fft.mul(r, &a, &b)), fft.last_error().to_bits())
+        });
+        assert_eq!(r, want);
+        let r = on_pools(|| product(10000, |r| Ntt::new().sqr(r, &a)));
+        assert_eq!(r, want_sqr);
+        let r = on_pools(|| product(9100, |r| Ssa::new().mul(r, &a, &b)));
+        assert_eq!(r, want);
+        on_pools(|| {
+            let mut dwt = Dwt::new(3, 600_000);
+            let mut x = dwt.residue(&a);
+            let y = dwt.residue(&b);
+            dwt.mul(&mut x, &y);
+            dwt.sqr(&mut x);
+            (dwt.to_limbs(&x), dwt.last_error().to_bits())
+        });
+    }
+}
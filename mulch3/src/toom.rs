@@ -0,0 +1,589 @@
+#![warn(rust_2018_idioms)]
+
+// Multiplication of any size. Small operands go to the unrolled kernels
+// or the basecase, bigger ones to Karatsuba, Toom-3 and Toom-4 as they
+// cross the thresholds, and operands of very different lengths are cut
+// into pieces the size of the shorter one.
+//
+// Everything temporary comes out of a Workspace, which the caller keeps
+// around so that repeated multiplications of the same size, like the
+// squarings in an exponentiation, don't allocate. Sub-products big
+// enough to go on the thread pool each get a slice of it of their own,
+// so it's sized for as many of them as run at once.
+
+use std::cmp::max;
+
+use rayon::prelude::*;
+
+use crate::access::DefaultAccess;
+use crate::basecase::{mul_basecase, sqr_basecase};
+use crate::fft::Fft;
+use crate::limbs::{add_to, sub_from, copy_from, normalized_len, Signed};
+use crate::threads;
+use crate::{mul_kernel, sqr_kernel};
+
+// Sizes, in limbs of the shorter operand, at which each algorithm takes
+// over from the one before. The FFT only ever takes the whole product,
+// the Tooms also take the pieces of bigger ones. These are the defaults,
+// Thresholds::current has this machine's if it's been tuned, see tune.rs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Thresholds {
+    pub karatsuba: usize,
+    pub toom3: usize,
+    pub toom4: usize,
+    pub fft: usize,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {karatsuba: 24, toom3: 96, toom4: 256, fft: 5000}
+    }
+}
+
+impl Thresholds {
+    pub fn check(&self) {
+        if let Err(e) = self.validate() {
+            panic!("{}", e);
+        }
+    }
+
+    // Karatsuba needs the halves to be smaller than the whole, which they
+    // aren't below 4 limbs.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.karatsuba < 4 {
+            return Err(String::from("karatsuba threshold must be at least 4"));
+        }
+        if self.toom3 < self.karatsuba {
+            return Err(String::from("toom3 threshold below karatsuba's"));
+        }
+        if self.toom4 < self.toom3 {
+            return Err(String::from("toom4 threshold below toom3's"));
+        }
+        if self.fft < self.toom4 {
+            return Err(String::from("fft threshold below toom4's"));
+        }
+        return Ok(());
+    }
+}
+
+pub struct Workspace {
+    thresholds: Thresholds,
+    scratch: Vec<u64>,
+    // for products past the FFT threshold, made on the first one
+    fft: Option<Fft>,
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Workspace {
+    pub fn new() -> Self {
+        Self::with_thresholds(Thresholds::current())
+    }
+
+    pub fn with_thresholds(thresholds: Thresholds) -> Self {
+        thresholds.check();
+        Workspace {thresholds, scratch: Vec::new(), fft: None}
+    }
+
+    pub fn thresholds(&self) -> &Thresholds {
+        &self.thresholds
+    }
+
+    // Makes room for operands up to n limbs, so the first multiplication
+    // doesn't have to.
+    pub fn reserve(&mut self, n: usize) {
+        let need = scratch_len(&self.thresholds, n);
+        if self.scratch.len() < need {
+            self.scratch.resize(need, 0);
+        }
+    }
+
+    // out = a * b in the low a.len() + b.len() limbs of out.
+    pub fn mul(&mut self, out: &mut [u64], a: &[u64], b: &[u64]) {
+        assert!(out.len() >= a.len() + b.len());
+        let (a, b) = match a.len() >= b.len() {
+            true => (a, b),
+            false => (b, a),
+        };
+        if b.is_empty() {
+            copy_from(&mut out[..a.len()], &[]);
+            return;
+        }
+        if b.len() >= self.thresholds.fft {
+            self.fft.get_or_insert_with(Fft::new).mul(out, a, b);
+            return;
+        }
+        self.reserve(a.len());
+        mul_into(&self.thresholds, out, a, b, false, &mut self.scratch);
+    }
+
+    // out = a * a in the low 2 * a.len() limbs of out.
+    pub fn sqr(&mut self, out: &mut [u64], a: &[u64]) {
+        assert!(out.len() >= 2 * a.len());
+        if a.is_empty() {
+            return;
+        }
+        if a.len() >= self.thresholds.fft {
+            self.fft.get_or_insert_with(Fft::new).sqr(out, a);
+            return;
+        }
+        self.reserve(a.len());
+        mul_into(&self.thresholds, out, a, a, true, &mut self.scratch);
+    }
+}
+
+// Enough scratch for any product whose longer operand is at most n limbs.
+// Every recursive call is on at most n/2 + 1 limbs, so it's this level's
+// biggest need plus the next level's, or the next level's once for each
+// sub-product where they go on the pool.
+fn scratch_len(t: &Thresholds, n: usize) -> usize {
+    if n < t.karatsuba {
+        return 0;
+    }
+    let h = n.div_ceil(2);
+    let (k3, k4) = (n.div_ceil(3), n.div_ceil(4));
+    let here = max(
+        max(4 * h + 4, 2 * h),
+        max(toom_len(3, k3), toom_len(4, k4)),
+    );
+    let below = scratch_len(t, h + 1);
+    let mut next = below;
+    if h >= threads::PARALLEL_LIMBS {
+        next = max(next, 3 * below);
+    }
+    for &(pieces, k) in [(3, k3), (4, k4)].iter() {
+        if k + 1 >= threads::PARALLEL_LIMBS {
+            next = max(next, (2 * pieces - 1) * point_task_len(t, k + 1));
+        }
+    }
+    return here + next;
+}
+
+// The slots Toom-K interpolates in, 2K - 1 points and two temporaries,
+// plus the two evaluations being multiplied.
+fn toom_len(pieces: usize, k: usize) -> usize {
+    (2 * pieces + 1) * (2 * k + 3) + 2 * (k + 1)
+}
+
+// What a point product on the pool needs, evaluations of e limbs and
+// scratch for multiplying them.
+fn point_task_len(t: &Thresholds, e: usize) -> usize {
+    2 * e + scratch_len(t, e)
+}
+
+// out = a * b, or a * a if square, with a.len() >= b.len() >= 1.
+fn mul_into(t: &Thresholds, out: &mut [u64], a: &[u64], b: &[u64], square: bool, scratch: &mut [u64]) {
+    let (n, m) = (a.len(), b.len());
+    debug_assert!(n >= m && m >= 1);
+    let out = &mut out[..n + m];
+    if m < t.karatsuba {
+        if square {
+            match sqr_kernel(n) {
+                Some(k) => k(out, a),
+                None => sqr_basecase::<DefaultAccess>(out, a),
+            }
+        } else if n == m && mul_kernel(n).is_some() {
+            mul_kernel(n).unwrap()(out, a, b);
+        } else {
+            mul_basecase::<DefaultAccess>(out, a, b);
+        }
+        return;
+    }
+    // Each Toom needs every piece of b to be non-empty.
+    if m >= t.toom4 && m > 3 * n.div_ceil(4) {
+        toom4(t, out, a, b, square, scratch);
+    } else if m >= t.toom3 && m > 2 * n.div_ceil(3) {
+        toom3(t, out, a, b, square, scratch);
+    } else if m > n.div_ceil(2) {
+        karatsuba(t, out, a, b, square, scratch);
+    } else {
+        unbalanced(t, out, a, b, scratch);
+    }
+}
+
+// a cut into pieces the length of b, each multiplied by b and added in.
+fn unbalanced(t: &Thresholds, out: &mut [u64], a: &[u64], b: &[u64], scratch: &mut [u64]) {
+    let (n, m) = (a.len(), b.len());
+    let (tmp, scratch) = scratch.split_at_mut(2 * m);
+    copy_from(out, &[]);
+    for (i, c) in a.chunks(m).enumerate() {
+        let tmp = &mut tmp[..c.len() + m];
+        if c.len() == m {
+            mul_into(t, tmp, c, b, false, scratch);
+        } else {
+            mul_into(t, tmp, b, c, false, scratch);
+        }
+        let carry = add_to(&mut out[i * m..n + m], tmp);
+        debug_assert!(!carry);
+    }
+}
+
+// a0 b0 + ((a0 + a1)(b0 + b1) - a0 b0 - a1 b1) x + a1 b1 x^2 with x the
+// limb base to the h.
+fn karatsuba(t: &Thresholds, out: &mut [u64], a: &[u64], b: &[u64], square: bool, scratch: &mut [u64]) {
+    let (n, m) = (a.len(), b.len());
+    let h = n.div_ceil(2);
+    let (a0, a1) = a.split_at(h);
+    let (b0, b1) = b.split_at(h);
+    let (sa, scratch) = scratch.split_at_mut(h + 1);
+    let (sb, scratch) = scratch.split_at_mut(h + 1);
+    let (z, scratch) = scratch.split_at_mut(2 * h + 2);
+    copy_from(sa, a0);
+    add_to(sa, a1);
+    if !square {
+        copy_from(sb, b0);
+        add_to(sb, b1);
+    }
+    let (sa, sb): (&[u64], &[u64]) = if square { (sa, sa) } else { (sa, sb) };
+    {
+        let (lo, hi) = out.split_at_mut(2 * h);
+        if h >= threads::PARALLEL_LIMBS && threads::parallel() {
+            // The three products are independent, each with its own scratch.
+            let need = scratch_len(t, h + 1);
+            let (s0, scratch) = scratch.split_at_mut(need);
+            let (s1, scratch) = scratch.split_at_mut(need);
+            let s2 = &mut scratch[..need];
+            rayon::join(
+                || mul_into(t, lo, a0, b0, square, s0),
+                || rayon::join(
+                    || mul_into(t, hi, a1, b1, square, s1),
+                    || mul_into(t, z, sa, sb, square, s2),
+                ),
+            );
+        } else {
+            mul_into(t, lo, a0, b0, square, scratch);
+            mul_into(t, hi, a1, b1, square, scratch);
+            mul_into(t, z, sa, sb, square, scratch);
+        }
+    }
+    let borrow = sub_from(z, &out[..2 * h]) | sub_from(z, &out[2 * h..n + m]);
+    debug_assert!(!borrow);
+    let len = normalized_len(z);
+    let carry = add_to(&mut out[h..n + m], &z[..len]);
+    debug_assert!(!carry);
+}
+
+#[derive(Clone, Copy)]
+enum Point {
+    Zero,
+    One,
+    MinusOne,
+    Two,
+    MinusTwo,
+    // 2^(K-1) times the value at 1/2, which keeps it whole
+    Half,
+    Infinity,
+}
+
+// The polynomial with the given coefficients at the point, by Horner's
+// rule, lowest coefficient last except for Half.
+fn evaluate(out: &mut Signed<'_>, pieces: &[&[u64]], point: Point) {
+    let last = pieces.len() - 1;
+    match point {
+        Point::Zero => out.set(false, pieces[0]),
+        Point::Infinity => out.set(false, pieces[last]),
+        Point::One | Point::MinusOne => {
+            out.set(false, pieces[0]);
+            for (i, p) in pieces.iter().enumerate().skip(1) {
+                let neg = matches!(point, Point::MinusOne) && i % 2 == 1;
+                out.add(neg, p);
+            }
+        }
+        Point::Two | Point::MinusTwo => {
+            out.set(false, pieces[last]);
+            for p in pieces[..last].iter().rev() {
+                out.mul_small(2);
+                if matches!(point, Point::MinusTwo) {
+                    out.neg = !out.neg;
+                }
+                out.add(false, p);
+            }
+        }
+        Point::Half => {
+            out.set(false, pieces[0]);
+            for p in pieces[1..].iter() {
+                out.mul_small(2);
+                out.add(false, p);
+            }
+        }
+    }
+}
+
+// r = p(point) q(point), or p(point)^2 if square. At zero and infinity
+// that's just the end pieces.
+#[allow(clippy::too_many_arguments)]
+fn point_product(t: &Thresholds, r: &mut Signed<'_>, ap: &[&[u64]], bp: &[&[u64]],
+    point: Point, square: bool, ea: &mut [u64], eb: &mut [u64], scratch: &mut [u64])
+{
+    let e = ea.len();
+    let last = ap.len() - 1;
+    copy_from(r.v, &[]);
+    match point {
+        Point::Zero => {
+            mul_into(t, r.v, ap[0], bp[0], square, scratch);
+            r.neg = false;
+            return;
+        }
+        Point::Infinity => {
+            mul_into(t, r.v, ap[last], bp[last], square, scratch);
+            r.neg = false;
+            return;
+        }
+        _ => {}
+    }
+    let mut pa = Signed::new(ea);
+    evaluate(&mut pa, ap, point);
+    if square {
+        mul_into(t, &mut r.v[..2 * e], pa.v, pa.v, true, scratch);
+        r.neg = false;
+    } else {
+        let mut pb = Signed::new(eb);
+        evaluate(&mut pb, bp, point);
+        mul_into(t, &mut r.v[..2 * e], pa.v, pb.v, false, scratch);
+        r.neg = pa.neg != pb.neg && normalized_len(r.v) > 0;
+    }
+}
+
+// The point products Toom-K interpolates from, each into its slot. Big
+// ones go on the pool, each with evaluations and scratch of its own cut
+// from scratch.
+#[allow(clippy::too_many_arguments)]
+fn point_products(t: &Thresholds, rs: &mut [(&mut Signed<'_>, Point)], ap: &[&[u64]], bp: &[&[u64]],
+    square: bool, ea: &mut [u64], eb: &mut [u64], scratch: &mut [u64])
+{
+    let e = ea.len();
+    if e >= threads::PARALLEL_LIMBS && threads::parallel() {
+        let task = point_task_len(t, e);
+        debug_assert!(scratch.len() >= rs.len() * task);
+        rs.par_iter_mut().zip(scratch.par_chunks_mut(task)).for_each(|((r, point), s)| {
+            let (ea, s) = s.split_at_mut(e);
+            let (eb, s) = s.split_at_mut(e);
+            point_product(t, r, ap, bp, *point, square, ea, eb, s);
+        });
+    } else {
+        for (r, point) in rs.iter_mut() {
+            point_product(t, r, ap, bp, *point, square, ea, eb, scratch);
+        }
+    }
+}
+
+// out = sum of c[i] x^i with x the limb base to the k.
+fn recompose(out: &mut [u64], c: &[&Signed<'_>], k: usize) {
+    copy_from(out, &[]);
+    for (i, ci) in c.iter().enumerate() {
+        debug_assert!(!ci.neg);
+        let len = normalized_len(ci.v);
+        let carry = add_to(&mut out[i * k..], &ci.v[..len]);
+        debug_assert!(!carry);
+    }
+}
+
+// The coefficient pieces, a0 first, the last one possibly shorter.
+fn split<'a>(a: &'a [u64], k: usize, pieces: &mut [&'a [u64]]) {
+    for (i, p) in pieces.iter_mut().enumerate() {
+        *p = &a[i * k..((i + 1) * k).min(a.len())];
+    }
+}
+
+// Evaluation at 0, 1, -1, 2 and infinity.
+fn toom3(t: &Thresholds, out: &mut [u64], a: &[u64], b: &[u64], square: bool, scratch: &mut [u64]) {
+    let k = a.len().div_ceil(3);
+    let (l, e) = (2 * k + 3, k + 1);
+    let mut ap: [&[u64]; 3] = [&[]; 3];
+    let mut bp: [&[u64]; 3] = [&[]; 3];
+    split(a, k, &mut ap);
+    split(b, k, &mut bp);
+    let (slots, scratch) = scratch.split_at_mut(6 * l);
+    let (ea, scratch) = scratch.split_at_mut(e);
+    let (eb, scratch) = scratch.split_at_mut(e);
+    let mut it = slots.chunks_exact_mut(l).map(Signed::new);
+    let (mut r0, mut r1, mut rm1, mut r2, mut rinf, mut c2) = (
+        it.next().unwrap(), it.next().unwrap(), it.next().unwrap(),
+        it.next().unwrap(), it.next().unwrap(), it.next().unwrap(),
+    );
+
+    point_products(t, &mut [
+        (&mut r0, Point::Zero), (&mut r1, Point::One), (&mut rm1, Point::MinusOne),
+        (&mut r2, Point::Two), (&mut rinf, Point::Infinity),
+    ], &ap, &bp, square, ea, eb, scratch);
+
+    // c2 = (r1 + rm1)/2 - c0 - c4, r1 = c1 + c3
+    c2.set(r1.neg, r1.v);
+    c2.add_signed(&rm1);
+    c2.div_exact(2);
+    c2.sub_signed(&r0);
+    c2.sub_signed(&rinf);
+    r1.sub_signed(&rm1);
+    r1.div_exact(2);
+    // r2 = c1 + 4 c3 once c0, c2 and c4 are out of it
+    r2.sub_signed(&r0);
+    rm1.set_mul(&c2, 4);
+    r2.sub_signed(&rm1);
+    rm1.set_mul(&rinf, 16);
+    r2.sub_signed(&rm1);
+    r2.div_exact(2);
+    // c3 into r2, c1 into r1
+    r2.sub_signed(&r1);
+    r2.div_exact(3);
+    r1.sub_signed(&r2);
+
+    recompose(out, &[&r0, &r1, &c2, &r2, &rinf], k);
+}
+
+// Evaluation at 0, 1, -1, 2, -2, 1/2 and infinity.
+fn toom4(t: &Thresholds, out: &mut [u64], a: &[u64], b: &[u64], square: bool, scratch: &mut [u64]) {
+    let k = a.len().div_ceil(4);
+    let (l, e) = (2 * k + 3, k + 1);
+    let mut ap: [&[u64]; 4] = [&[]; 4];
+    let mut bp: [&[u64]; 4] = [&[]; 4];
+    split(a, k, &mut ap);
+    split(b, k, &mut bp);
+    let (slots, scratch) = scratch.split_at_mut(9 * l);
+    let (ea, scratch) = scratch.split_at_mut(e);
+    let (eb, scratch) = scratch.split_at_mut(e);
+    let mut it = slots.chunks_exact_mut(l).map(Signed::new);
+    let (mut r0, mut r1, mut rm1, mut r2, mut rm2, mut rh, mut rinf, mut c2, mut c4) = (
+        it.next().unwrap(), it.next().unwrap(), it.next().unwrap(),
+        it.next().unwrap(), it.next().unwrap(), it.next().unwrap(),
+        it.next().unwrap(), it.next().unwrap(), it.next().unwrap(),
+    );
+
+    point_products(t, &mut [
+        (&mut r0, Point::Zero), (&mut r1, Point::One), (&mut rm1, Point::MinusOne),
+        (&mut r2, Point::Two), (&mut rm2, Point::MinusTwo), (&mut rh, Point::Half),
+        (&mut rinf, Point::Infinity),
+    ], &ap, &bp, square, ea, eb, scratch);
+
+    // Even and odd parts at 1 and 2:
+    // c2 = c2 + c4 + ..., r1 = c1 + c3 + c5
+    // c4 = c0 + 4 c2 + 16 c4 + 64 c6, r2 = c1 + 4 c3 + 16 c5
+    c2.set(r1.neg, r1.v);
+    c2.add_signed(&rm1);
+    c2.div_exact(2);
+    r1.sub_signed(&rm1);
+    r1.div_exact(2);
+    c4.set(r2.neg, r2.v);
+    c4.add_signed(&rm2);
+    c4.div_exact(2);
+    r2.sub_signed(&rm2);
+    r2.div_exact(4);
+
+    // The even coefficients: c2 + c4 and c2 + 4 c4.
+    c2.sub_signed(&r0);
+    c2.sub_signed(&rinf);
+    c4.sub_signed(&r0);
+    rm1.set_mul(&rinf, 64);
+    c4.sub_signed(&rm1);
+    c4.div_exact(4);
+    c4.sub_signed(&c2);
+    c4.div_exact(3);
+    c2.sub_signed(&c4);
+
+    // rh = 16 c1 + 4 c3 + c5 once the even ones are out of it.
+    rm1.set_mul(&r0, 64);
+    rh.sub_signed(&rm1);
+    rm1.set_mul(&c2, 16);
+    rh.sub_signed(&rm1);
+    rm1.set_mul(&c4, 4);
+    rh.sub_signed(&rm1);
+    rh.sub_signed(&rinf);
+    rh.div_exact(2);
+
+    // With O1 = c1 + c3 + c5 in r1, O2 = c1 + 4 c3 + 16 c5 in r2 and
+    // H in rh: U = (H - O1)/3 = 5 c1 + c3 and V = (O2 - O1)/3 = c3 + 5 c5.
+    rh.sub_signed(&r1);
+    rh.div_exact(3);
+    r2.sub_signed(&r1);
+    r2.div_exact(3);
+    // c3 = (5 O1 - U - V)/3 into rm2
+    rm2.set_mul(&r1, 5);
+    rm2.sub_signed(&rh);
+    rm2.sub_signed(&r2);
+    rm2.div_exact(3);
+    // W = c1 - c5 = (U - V)/5 into rh, S = c1 + c5 into r1
+    rh.sub_signed(&r2);
+    rh.div_exact(5);
+    r1.sub_signed(&rm2);
+    // c1 = (S + W)/2 into r2, c5 = (S - W)/2 into r1
+    r2.set(r1.neg, r1.v);
+    r2.add_signed(&rh);
+    r2.div_exact(2);
+    r1.sub_signed(&rh);
+    r1.div_exact(2);
+
+    recompose(out, &[&r0, &r2, &c2, &rm2, &c4, &r1, &rinf], k);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{gmp_mul, limbs};
+
+    fn check(ws: &mut Workspace, sizes: &[(usize, usize)], state: &mut u64) {
+        for &(n, m) in sizes.iter() {
+            let a = limbs(n, state);
+            let b = limbs(m, state);
+            let mut out = vec![0u64; n + m];
+            ws.mul(&mut out, &a, &b);
+            assert_eq!(out, gmp_mul(&a, &b), "{} x {} with {:?}", n, m, ws.thresholds());
+            let mut sq = vec![0u64; 2 * n];
+            ws.sqr(&mut sq, &a);
+            assert_eq!(sq, gmp_mul(&a, &a), "{}^2 with {:?}", n, ws.thresholds());
+        }
+    }
+
+    #[test]
+    fn every_algorithm() {
+        let mut state = 0x853c_49e6_748f_ea9b;
+        let small = Thresholds {karatsuba: 4, toom3: 9, toom4: 16, fft: usize::MAX};
+        let mut sizes = Vec::new();
+        for n in 1..80 {
+            sizes.push((n, n));
+            sizes.push((n, n / 2 + 1));
+            sizes.push((n, n * 3 / 4 + 1));
+            sizes.push((n, 1 + n / 5));
+        }
+        let fft = Thresholds {fft: 40, ..small};
+        for &t in [small, fft, Thresholds {karatsuba: 4, toom3: 4, toom4: 4, fft: usize::MAX}].iter() {
+            check(&mut Workspace::with_thresholds(t), &sizes, &mut state);
+        }
+        let big = [(300, 300), (1000, 1000), (1000, 700), (1000, 333), (2000, 41), (513, 512)];
+        check(&mut Workspace::new(), &big, &mut state);
+    }
+
+    #[test]
+    fn workspace_is_reused() {
+        let mut ws = Workspace::new();
+        ws.reserve(1000);
+        let before = ws.scratch.as_ptr();
+        let mut state = 1;
+        check(&mut ws, &[(1000, 1000), (999, 500), (10, 3)], &mut state);
+        assert_eq!(ws.scratch.as_ptr(), before);
+    }
+
+    // With the sub-products on the pool too.
+    #[test]
+    fn workspace_is_reused_in_parallel() {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        let karatsuba = Thresholds {karatsuba: 24, toom3: usize::MAX, toom4: usize::MAX, fft: usize::MAX};
+        let toom = Thresholds {fft: usize::MAX, ..Thresholds::default()};
+        for &t in [karatsuba, toom].iter() {
+            let mut ws = Workspace::with_thresholds(t);
+            ws.reserve(5000);
+            let before = ws.scratch.as_ptr();
+            let mut state = 2;
+            pool.install(|| check(&mut ws, &[(5000, 5000), (5000, 4100), (2500, 2500)], &mut state));
+            assert_eq!(ws.scratch.as_ptr(), before, "{:?}", t);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 4")]
+    fn tiny_karatsuba() {
+        Workspace::with_thresholds(Thresholds {karatsuba: 3, toom3: 9, toom4: 16, fft: usize::MAX});
+    }
+}
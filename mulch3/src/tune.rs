@@ -0,0 +1,223 @@
+#![warn(rust_2018_idioms)]
+
+// Thresholds for the machine at hand. tune times each algorithm against
+// the one it would take over from at growing sizes, and the first size
+// where it wins twice running is its threshold. The result goes in a
+// thresholds file, which Thresholds::current reads once and keeps.
+//
+// The file names the build it was tuned on, and one from any other build
+// is passed over for the defaults: the crossovers move with the code,
+// the target and the features, see build_id in build/main.rs. It's
+// MULCH3_THRESHOLDS, or mulch3-thresholds.txt in the current directory,
+// and looks like
+//
+//     # mulch3 thresholds
+//     build = 0.1.0-0123456789abcdef
+//     karatsuba = 24
+//     toom3 = 96
+//     toom4 = 256
+//     fft = 5000
+//
+// with never for an algorithm that never won.
+
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::testing::next;
+use crate::toom::{Thresholds, Workspace};
+
+pub const BUILD_ID: &str = env!("MULCH3_BUILD_ID");
+
+const DEFAULT_FILE: &str = "mulch3-thresholds.txt";
+
+const NAMES: [&str; 4] = ["karatsuba", "toom3", "toom4", "fft"];
+
+const NEVER: usize = usize::MAX;
+
+// Every timing runs for at least SLICE, and the best of REPEATS counts.
+const SLICE: Duration = Duration::from_millis(2);
+const REPEATS: usize = 5;
+
+pub fn thresholds_file() -> String {
+    env::var("MULCH3_THRESHOLDS").unwrap_or_else(|_| String::from(DEFAULT_FILE))
+}
+
+impl Thresholds {
+    // The thresholds file's, or the defaults without one. A file that's
+    // there but no good gets a warning.
+    pub fn current() -> Thresholds {
+        static CURRENT: OnceLock<Thresholds> = OnceLock::new();
+        return *CURRENT.get_or_init(|| {
+            let file = thresholds_file();
+            let parsed = match fs::read_to_string(&file) {
+                Ok(s) => Thresholds::parse(&s),
+                Err(e) if e.kind() == ErrorKind::NotFound => return Thresholds::default(),
+                Err(e) => Err(e.to_string()),
+            };
+            match parsed {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("mulch3: ignoring {}, using the default thresholds: {}", file, e);
+                    Thresholds::default()
+                }
+            }
+        });
+    }
+
+    // The thresholds file for these, for this build.
+    pub fn to_file(&self) -> String {
+        let mut s = format!("# mulch3 thresholds\nbuild = {}\n", BUILD_ID);
+        let values = [self.karatsuba, self.toom3, self.toom4, self.fft];
+        for (name, &v) in NAMES.iter().zip(values.iter()) {
+            if v == NEVER {
+                s += &format!("{} = never\n", name);
+            } else {
+                s += &format!("{} = {}\n", name, v);
+            }
+        }
+        return s;
+    }
+
+    // A thresholds file, which has to be for this build.
+    pub fn parse(s: &str) -> Result<Thresholds, String> {
+        let mut build = None;
+        let mut values = [None; 4];
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(format!("line {}: expected name = value", i + 1)),
+            };
+            if key == "build" {
+                build = Some(value);
+                continue;
+            }
+            let slot = match NAMES.iter().position(|&name| name == key) {
+                Some(slot) => slot,
+                None => return Err(format!("line {}: no threshold called {}", i + 1, key)),
+            };
+            values[slot] = match value {
+                "never" => Some(NEVER),
+                _ => Some(value.parse().map_err(|_| format!("line {}: {} isn't a size", i + 1, value))?),
+            };
+        }
+        match build {
+            Some(b) if b == BUILD_ID => {}
+            Some(b) => return Err(format!("tuned for build {}, this is {}", b, BUILD_ID)),
+            None => return Err(String::from("no build line")),
+        }
+        let get = |i: usize| values[i].ok_or_else(|| format!("no {} threshold", NAMES[i]));
+        let t = Thresholds {karatsuba: get(0)?, toom3: get(1)?, toom4: get(2)?, fft: get(3)?};
+        t.validate()?;
+        return Ok(t);
+    }
+}
+
+// Times the algorithms on this machine up to max limbs and returns where
+// each takes over, never past max. progress gets a line per size.
+pub fn tune(max: usize, progress: &mut (dyn FnMut(&str) + Send)) -> Thresholds {
+    // On one thread, the thresholds are for a product on one core.
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().expect("no thread pool");
+    return pool.install(|| {
+        let mut t = Thresholds {karatsuba: NEVER, toom3: NEVER, toom4: NEVER, fft: NEVER};
+        t.karatsuba = crossover("karatsuba", 4, max, progress, move |n| Thresholds {karatsuba: n, ..t});
+        t.toom3 = crossover("toom3", t.karatsuba, max, progress, move |n| Thresholds {toom3: n, ..t});
+        t.toom4 = crossover("toom4", t.toom3, max, progress, move |n| Thresholds {toom4: n, ..t});
+        t.fft = crossover("fft", t.toom4, max, progress, move |n| Thresholds {fft: n, ..t});
+        return t;
+    });
+}
+
+// The first size from from on where with(n) beats the thresholds before
+// it twice running, or never.
+fn crossover(name: &str, from: usize, max: usize, progress: &mut (dyn FnMut(&str) + Send),
+    with: impl Fn(usize) -> Thresholds) -> usize
+{
+    let before = with(NEVER);
+    let (mut n, mut first, mut wins) = (from, NEVER, 0);
+    while n <= max {
+        let (old, new) = (time(before, n), time(with(n), n));
+        progress(&format!("{:>9} at {:>6} limbs: {:.3e}s, before {:.3e}s", name, n, new, old));
+        if new < old {
+            if wins == 0 {
+                first = n;
+            }
+            wins += 1;
+            if wins == 2 {
+                return first;
+            }
+        } else {
+            wins = 0;
+        }
+        n = (n + 1).max(n / 8 * 9);
+    }
+    return NEVER;
+}
+
+// Seconds for an n-limb squaring, which is what exponentiation does
+// most. Random limbs, the same for every timing at n.
+fn time(t: Thresholds, n: usize) -> f64 {
+    let mut state = 0x9e37_79b9_7f4a_7c15 ^ n as u64;
+    let a: Vec<u64> = (0..n).map(|_| next(&mut state)).collect();
+    let mut out = vec![0u64; 2 * n];
+    let mut ws = Workspace::with_thresholds(t);
+    ws.sqr(&mut out, &a);
+    let mut best = f64::MAX;
+    for _ in 0..REPEATS {
+        let (start, mut count) = (Instant::now(), 0);
+        while count == 0 || start.elapsed() < SLICE {
+            ws.sqr(&mut out, &a);
+            count += 1;
+        }
+        best = best.min(start.elapsed().as_secs_f64() / count as f64);
+    }
+    return best;
+}
+
+// tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_round_trip() {
+        let t = Thresholds {karatsuba: 20, toom3: 100, toom4: 300, fft: NEVER};
+        assert_eq!(Thresholds::parse(&t.to_file()), Ok(t));
+        assert_eq!(Thresholds::parse(&Thresholds::default().to_file()), Ok(Thresholds::default()));
+    }
+
+    #[test]
+    fn bad_files() {
+        let file = Thresholds::default().to_file();
+        let stale = file.replace(BUILD_ID, "0.0.0-0000000000000000");
+        assert!(Thresholds::parse(&stale).unwrap_err().starts_with("tuned for build 0.0.0"));
+        let unbuilt = file.replace(&format!("build = {}\n", BUILD_ID), "");
+        assert_eq!(Thresholds::parse(&unbuilt), Err(String::from("no build line")));
+        let missing = file.replace("toom4 = 256\n", "");
+        assert_eq!(Thresholds::parse(&missing), Err(String::from("no toom4 threshold")));
+        let unknown = file.clone() + "toom5 = 1000\n";
+        assert_eq!(Thresholds::parse(&unknown), Err(String::from("line 7: no threshold called toom5")));
+        let garbled = file.replace("fft = 5000", "fft = lots");
+        assert_eq!(Thresholds::parse(&garbled), Err(String::from("line 6: lots isn't a size")));
+        let backwards = file.replace("toom3 = 96", "toom3 = 300");
+        assert_eq!(Thresholds::parse(&backwards), Err(String::from("toom4 threshold below toom3's")));
+    }
+
+    #[test]
+    fn tunes_something_valid() {
+        let mut lines = 0;
+        let t = tune(40, &mut |_| lines += 1);
+        assert_eq!(t.validate(), Ok(()));
+        assert!(lines > 0);
+        for &v in [t.karatsuba, t.toom3, t.toom4, t.fft].iter() {
+            assert!(v == NEVER || (4..=40).contains(&v), "{:?}", t);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+#![warn(rust_2018_idioms)]
+
+// Batch mode: one candidate per line, each tested in a child process by
+// one of a pool of worker threads. For mid-size candidates a test per
+// core goes further than one test spread over the cores. A candidate
+// whose child was killed goes back on the queue, up to ATTEMPTS times,
+// and the results come out in input order tagged with their line.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, BufRead, Write};
+use std::process::Command;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+// Times a candidate is tried before giving up on it.
+const ATTEMPTS : u32 = 3;
+
+struct Job {
+    // place in the output, and line in the input
+    index : usize,
+    line : usize,
+    candidate : String,
+    attempts : u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Prime,
+    Composite,
+    Failed(String),
+}
+
+// Tests every candidate in input with jobs workers, each child built by
+// spawn for its candidate, and writes the results to out. Blank lines
+// and lines starting with # are skipped. Returns how many failed.
+pub fn run<S>(input : impl BufRead, jobs : usize, out : &mut dyn Write, spawn : S) -> io::Result<usize>
+    where S : Fn(&str) -> Command + Sync
+{
+    assert!(jobs >= 1, "need at least one worker");
+    let mut queue = VecDeque::new();
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        let candidate = line.trim();
+        if candidate.is_empty() || candidate.starts_with('#') {
+            continue;
+        }
+        queue.push_back(Job {index: queue.len(), line: i + 1, candidate: candidate.to_string(), attempts: 0});
+    }
+    let total = queue.len();
+    let queue = Mutex::new(queue);
+    let (results, received) = mpsc::channel();
+    let mut failed = 0;
+    thread::scope(|scope| -> io::Result<()> {
+        for _ in 0..jobs.min(total) {
+            let results = results.clone();
+            let (queue, spawn) = (&queue, &spawn);
+            // A worker stops once the queue is empty. Whoever puts a job
+            // back is still running, so it's never left behind.
+            scope.spawn(move || loop {
+                let mut job = match queue.lock().unwrap().pop_front() {
+                    Some(job) => job,
+                    None => return,
+                };
+                job.attempts += 1;
+                match test(&spawn, &job.candidate) {
+                    Some(outcome) => results.send((job, outcome)).unwrap(),
+                    None if job.attempts < ATTEMPTS => queue.lock().unwrap().push_back(job),
+                    None => {
+                        let outcome = Outcome::Failed(format!("killed {} times", job.attempts));
+                        results.send((job, outcome)).unwrap();
+                    }
+                }
+            });
+        }
+        drop(results);
+        // Results wait here until everything before them is out.
+        let mut waiting = BTreeMap::new();
+        let mut next = 0;
+        for (job, outcome) in received.iter() {
+            waiting.insert(job.index, (job, outcome));
+            while let Some((job, outcome)) = waiting.remove(&next) {
+                if let Outcome::Failed(_) = outcome {
+                    failed += 1;
+                }
+                writeln!(out, "{}: {} {}", job.line, job.candidate, describe(&outcome))?;
+                out.flush()?;
+                next += 1;
+            }
+        }
+        return Ok(());
+    })?;
+    return Ok(failed);
+}
+
+fn describe(outcome : &Outcome) -> String {
+    match outcome {
+        Outcome::Prime => String::from("prime"),
+        Outcome::Composite => String::from("composite"),
+        Outcome::Failed(why) => format!("failed: {}", why),
+    }
+}
+
+// The worker's verdict on the last line it prints.
+pub fn verdict(prime : bool) -> String {
+    return format!("result: {}", if prime { "prime" } else { "composite" });
+}
+
+// Runs the child for one candidate. None if it was killed, so it should
+// be tried again.
+fn test(spawn : &dyn Fn(&str) -> Command, candidate : &str) -> Option<Outcome> {
+    let output = match spawn(candidate).output() {
+        Ok(output) => output,
+        Err(e) => return Some(Outcome::Failed(e.to_string())),
+    };
+    // No exit code means it was killed by a signal.
+    output.status.code()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let last = stdout.lines().rev().find(|l| l.starts_with("result: "));
+    if output.status.success() {
+        if last == Some(verdict(true).as_str()) {
+            return Some(Outcome::Prime);
+        }
+        if last == Some(verdict(false).as_str()) {
+            return Some(Outcome::Composite);
+        }
+    }
+    // A panic's message is on the line after where it was, anything else
+    // gets the last thing the child said.
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines : Vec<&str> = stderr.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    let why = match lines.iter().position(|l| l.contains("panicked at")) {
+        Some(i) if i + 1 < lines.len() => lines[i + 1],
+        _ => lines.last().cloned().unwrap_or("no result"),
+    };
+    return Some(Outcome::Failed(format!("{} ({})", why, output.status)));
+}
+
+// tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // Children that answer from the candidate: prime if it has a p in it,
+    // composite if a c, and a k means kill yourself the first time.
+    fn shell(dir : &std::path::Path) -> impl Fn(&str) -> Command + Sync + '_ {
+        move |candidate : &str| {
+            let mark = dir.join(candidate);
+            let script = format!(
+                "case {c} in *k*) [ -e {m} ] || {{ touch {m}; kill -9 $$; }};; esac; \
+                 case {c} in *p*) echo '{p}';; *c*) echo '{q}';; *) echo oops >&2; exit 3;; esac",
+                c = candidate, m = mark.display(), p = verdict(true), q = verdict(false),
+            );
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(script);
+            command
+        }
+    }
+
+    fn batch(input : &str, jobs : usize, name : &str) -> (String, usize) {
+        let dir = std::env::temp_dir().join(format!("hazelprime-batch-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut out = Vec::new();
+        let failed = run(input.as_bytes(), jobs, &mut out, shell(&dir)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        return (String::from_utf8(out).unwrap(), failed);
+    }
+
+    #[test]
+    fn in_input_order() {
+        let input = "p1\n\n# comment\nc2\np3\nc4\np5\nc6\n";
+        let want = "1: p1 prime\n4: c2 composite\n5: p3 prime\n6: c4 composite\n7: p5 prime\n8: c6 composite\n";
+        for &jobs in [1, 3, 8].iter() {
+            assert_eq!(batch(input, jobs, &format!("order{}", jobs)), (String::from(want), 0));
+        }
+    }
+
+    #[test]
+    fn killed_are_requeued() {
+        let (out, failed) = batch("pk1\nc2\nck3\n", 2, "killed");
+        assert_eq!(out, "1: pk1 prime\n2: c2 composite\n3: ck3 composite\n");
+        assert_eq!(failed, 0);
+    }
+
+    #[test]
+    fn failures_are_reported() {
+        let (out, failed) = batch("p1\nx2\n", 2, "failed");
+        assert_eq!(failed, 1);
+        assert!(out.starts_with("1: p1 prime\n2: x2 failed: oops"), "{}", out);
+    }
+
+    #[test]
+    fn killed_every_time() {
+        let mut out = Vec::new();
+        let kill = |_ : &str| {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg("kill -9 $$");
+            command
+        };
+        assert_eq!(run("pk1\n".as_bytes(), 1, &mut out, kill).unwrap(), 1);
+        assert_eq!(String::from_utf8(out).unwrap(), format!("1: pk1 failed: killed {} times\n", ATTEMPTS));
+    }
+}
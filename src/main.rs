@@ -3,19 +3,34 @@
 mod proth;
 use proth::{Proth};
 mod proth_gmp;
+mod proth_generic;
+mod proth_dwt;
+mod batch;
+use hazelprime_bootstrap::big::{Native, Native32, Rug};
 
 mod parser;
 
-use clap::{Arg, App};
+use clap::{Arg, App, AppSettings, SubCommand};
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::process::{self, Command};
+
+fn positive(s : String) -> Result<(), String> {
+    match s.parse::<usize>() {
+        Ok(t) if t >= 1 => Ok(()),
+        _ => Err(String::from("must be a positive number")),
+    }
+}
 
 fn main() {
     let matches = App::new("Hazel's Primality Tester")
         .version("0.1.0")
         .author("Hazel Victoria Campbell")
         .about("Tests Proth numbers for primality")
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(Arg::with_name("number")
             .index(1)
-            .required(true)
+            .required_unless("batch")
             .help("A proth number of the format 943*2^3442990+1")
         )
         .arg(Arg::with_name("method")
@@ -24,20 +39,138 @@ fn main() {
             .value_name("METHOD")
             .help("Select primality testing algorithm")
             .takes_value(true)
-            .default_value("gmp2")
+            .possible_values(&[
+                "gmp_simple", "gmp_medium", "gmp_low", "gmp_barrett",
+                "gmp_montgomery", "gmp_proth", "fft", "ssa", "dwt", "proth", "prp",
+            ])
+            .default_value("gmp_proth")
+        )
+        .arg(Arg::with_name("backend")
+            .short("b")
+            .long("backend")
+            .value_name("BACKEND")
+            .help("Select the arithmetic used by the proth and prp methods")
+            .takes_value(true)
+            .possible_values(&["rug", "native", "native32"])
+            .default_value("rug")
+        )
+        .arg(Arg::with_name("threads")
+            .short("t")
+            .long("threads")
+            .value_name("THREADS")
+            .help("Threads for the fft, ssa and dwt methods, one per core by default")
+            .takes_value(true)
+            .validator(positive)
+        )
+        .arg(Arg::with_name("batch")
+            .long("batch")
+            .value_name("FILE")
+            .help("Test the proth numbers in FILE, one per line, or standard input for -")
+            .takes_value(true)
+            .conflicts_with("number")
+        )
+        .arg(Arg::with_name("jobs")
+            .short("j")
+            .long("jobs")
+            .value_name("JOBS")
+            .help("Numbers tested at once in batch mode, one per core by default")
+            .takes_value(true)
+            .validator(positive)
+        )
+        // A batch mode child, which ends with the result on a line of its own.
+        .arg(Arg::with_name("worker")
+            .long("worker")
+            .hidden(true)
+        )
+        .subcommand(SubCommand::with_name("tune")
+            .about("Finds mulch3's multiplication thresholds for this machine and writes them out")
+            .arg(Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("FILE")
+                .help("Where to write the thresholds, by default where mulch3 looks for them")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("max")
+                .long("max")
+                .value_name("LIMBS")
+                .help("The biggest operands to time")
+                .takes_value(true)
+                .validator(positive)
+                .default_value("32768")
+            )
         )
         .get_matches();
-    assert!(matches.is_present("number"));
+    if let Some(tune) = matches.subcommand_matches("tune") {
+        let file = tune.value_of("output").map_or_else(mulch3::thresholds_file, String::from);
+        let max : usize = tune.value_of("max").expect("What").parse().expect("What");
+        let thresholds = mulch3::tune(max, &mut |line : &str| println!("{}", line));
+        println!("{:?}", thresholds);
+        fs::write(&file, thresholds.to_file()).expect("Couldn't write the thresholds");
+        println!("wrote {}", file);
+        return;
+    }
+    let method : &str = matches.value_of("method").expect("What");
+    let backend : &str = matches.value_of("backend").expect("What");
+    if let Some(threads) = matches.value_of("threads") {
+        let threads : usize = threads.parse().expect("What");
+        mulch3::set_threads(threads).expect("Couldn't start the threads");
+    }
+    if let Some(file) = matches.value_of("batch") {
+        if proth_gmp::by_name(method).is_none() {
+            panic!("Batch mode runs the gmp_* methods, fft and ssa, not {}", method);
+        }
+        let jobs : usize = match matches.value_of("jobs") {
+            Some(jobs) => jobs.parse().expect("What"),
+            None => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        };
+        let exe = std::env::current_exe().expect("Couldn't find my own executable");
+        let spawn = |candidate : &str| {
+            let mut child = Command::new(&exe);
+            child.args(["--worker", "--threads", "1", "--method", method, candidate]);
+            child
+        };
+        let stdin = io::stdin();
+        let stdout = io::stdout();
+        let failed = if file == "-" {
+            batch::run(stdin.lock(), jobs, &mut stdout.lock(), spawn)
+        } else {
+            let input = File::open(file).expect("Couldn't open the batch file");
+            batch::run(BufReader::new(input), jobs, &mut stdout.lock(), spawn)
+        }.expect("Batch failed");
+        process::exit(if failed == 0 { 0 } else { 1 });
+    }
     let number_s : &str = matches.value_of("number").expect("What");
     let n : Proth =  parser::proth(number_s);
     println!("{:?}", n);
-    let method : &str = matches.value_of("method").expect("What");
-    match method {
-        "gmp_simple" => proth_gmp::simple(n),
-        "gmp_medium" => proth_gmp::medium(n),
-        "gmp_low" => proth_gmp::low(n),
-        "gmp_barrett" => proth_gmp::barrett(n),
-        _ => panic!("You must select a valid method: gmp_simple, gmp_medium, gmp_low, gmp_barrett")
+    if matches.is_present("worker") {
+        let method = proth_gmp::by_name(method).expect("Workers run the gmp_* methods, fft and ssa");
+        let (_, r_minus_p) = method(n);
+        println!("{}", batch::verdict(r_minus_p == -1));
+        return;
+    }
+    match (method, backend) {
+        ("gmp_simple", _) => { proth_gmp::simple(n); },
+        ("gmp_medium", _) => { proth_gmp::medium(n); },
+        ("gmp_low", _) => { proth_gmp::low(n); },
+        ("gmp_barrett", _) => { proth_gmp::barrett(n); },
+        ("gmp_montgomery", _) => { proth_gmp::montgomery(n); },
+        ("gmp_proth", _) => { proth_gmp::proth_form(n); },
+        ("fft", _) => { proth_gmp::fft(n); },
+        ("ssa", _) => { proth_gmp::ssa(n); },
+        ("dwt", _) => {
+            if let Err(e) = proth_dwt::proth(n) {
+                eprintln!("dwt: {}", e);
+                process::exit(1);
+            }
+        },
+        ("proth", "rug") => { proth_generic::proth::<Rug>(n); },
+        ("proth", "native") => { proth_generic::proth::<Native>(n); },
+        ("proth", "native32") => { proth_generic::proth::<Native32>(n); },
+        ("prp", "rug") => { proth_generic::prp::<Rug>(n); },
+        ("prp", "native") => { proth_generic::prp::<Native>(n); },
+        ("prp", "native32") => { proth_generic::prp::<Native32>(n); },
+        _ => panic!("You must select a valid method: gmp_simple, gmp_medium, gmp_low, gmp_barrett, gmp_montgomery, gmp_proth, fft, ssa, dwt, proth, prp; and backend: rug, native, native32")
     };
     println!("exit");
 }
@@ -0,0 +1,86 @@
+use crate::proth::Proth;
+use hazelprime_bootstrap::big::{Natural, Native};
+use hazelprime_bootstrap::primality;
+use mulch3::Dwt;
+
+// Proth's test with mulch3's DWT doing the arithmetic modulo N itself, so
+// nothing here goes through GMP. (N-1)/2 is t*2^(e-1), so that's a^t by
+// square and multiply and then e-1 squarings. An error if the DWT can't
+// do k*2^e+1 in double precision, which is for t in the millions and up.
+pub fn proth(n : Proth) -> Result<(bool, Native), String> {
+    let (t, e) = (n.t, n.e as usize);
+    assert!(t > 0);
+    assert!(e >= 2 && (e >= 32 || (t as u64) < (1u64 << e)), "Not a Proth number");
+    Dwt::validate(t as u64, e)?;
+    let a = primality::proth_base(t, e).expect("No small quadratic non-residue, N is probably a square");
+    println!("base: {}", a);
+    let mut dwt = Dwt::new(t as u64, e);
+    println!("words: {}", dwt.words());
+    let base = dwt.residue(&[a as u64]);
+    let mut r = base.clone();
+    for i in (0..31 - t.leading_zeros()).rev() {
+        dwt.sqr(&mut r);
+        if (t >> i) & 1 == 1 {
+            dwt.mul(&mut r, &base);
+        }
+    }
+    for i in 0..e - 1 {
+        dwt.sqr(&mut r);
+        if i % 100 == 0 || i < 100 {
+            println!("{}/{} {}", i, e - 1, (i as f32)/((e - 1) as f32));
+        }
+    }
+    println!("done");
+    println!("dwt: {} words, max round-off error {}", dwt.words(), dwt.max_error());
+    let r = Native::from_u64_limbs(&dwt.to_limbs(&r));
+    let n_full = (Native::from(t) << e) + 1u8;
+    // a = N shows up as r = 0, any other a dividing N just isn't -1
+    let prime = if r == Native::from(0u8) {
+        n_full == Native::from(a)
+    } else {
+        r == n_full - 1u8
+    };
+    if prime {
+        println!("Prime");
+    } else {
+        println!("Not prime");
+    }
+    return Ok((prime, r));
+}
+
+// tests
+
+#[cfg(test)]
+mod tests {
+    use crate::proth::Proth;
+    use crate::proth_dwt::proth;
+    use crate::proth_gmp::simple;
+    use hazelprime_bootstrap::primality;
+
+    #[test]
+    fn test_dwt_known_primes() {
+        // 13, 97, 65537, 786433, 5*2^26607+1
+        for &(t, e) in [(3, 2), (3, 5), (1, 16), (3, 18), (5, 26607)].iter() {
+            assert!(proth(Proth { t, e }).unwrap().0, "{}*2^{}+1", t, e);
+        }
+    }
+    #[test]
+    fn test_dwt_same_residue() {
+        // Both take base 3 for these, so the residues have to agree. 3
+        // divides 5*2^26606+1.
+        for &(t, e) in [(5, 26605), (5, 26606), (7, 1000), (1, 64), (11, 777), (13, 5000)].iter() {
+            let n = Proth { t, e };
+            assert_eq!(primality::proth_base(t, e as usize), Some(3));
+            let r = proth(n).unwrap();
+            assert!(!r.0, "{}*2^{}+1", t, e);
+            assert_eq!(r.1.to_string(), simple(n).0.to_string(), "{}*2^{}+1", t, e);
+        }
+    }
+    #[test]
+    fn test_dwt_too_big() {
+        // A Proth number, but k is too big for one bit per word.
+        let e = proth(Proth { t: 1048575, e: 1000 }).unwrap_err();
+        assert!(e.contains("too big for double precision"), "{}", e);
+        assert!(proth(Proth { t: 1 << 24, e: 100000 }).is_err());
+    }
+}
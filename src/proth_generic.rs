@@ -0,0 +1,69 @@
+use crate::proth::Proth;
+use hazelprime_bootstrap::big::Natural;
+use hazelprime_bootstrap::primality;
+
+// The same tests as proth_gmp, written over Natural so that --backend
+// picks the arithmetic.
+
+pub fn proth<T: Natural>(n : Proth) -> (bool, T) {
+    let (prime, a, r) = primality::proth::<T>(n.t, n.e as usize);
+    println!("base: {}", a);
+    if prime {
+        println!("Prime");
+    } else {
+        println!("Not prime");
+    }
+    return (prime, r);
+}
+
+pub fn prp<T: Natural>(n : Proth) -> (bool, T) {
+    let n_full : T = (T::from(n.t) << n.e as usize) + 1u8;
+    let (prp, r) = primality::fermat_prp(&n_full, 3);
+    if prp {
+        println!("Probable prime");
+    } else {
+        println!("Not prime");
+    }
+    return (prp, r);
+}
+
+// tests
+
+#[cfg(test)]
+mod tests {
+    use crate::proth::Proth;
+    use crate::proth_generic::{proth, prp};
+    use crate::proth_gmp::simple;
+    use hazelprime_bootstrap::big::{Native, Native32, Rug};
+
+    #[test]
+    fn test_proth_rug() {
+        let five_26607 = Proth { t: 5, e: 26607 };
+        assert!(proth::<Rug>(five_26607).0);
+    }
+    #[test]
+    fn test_proth_native() {
+        let five_26607 = Proth { t: 5, e: 26607 };
+        assert!(proth::<Native>(five_26607).0);
+    }
+    #[test]
+    fn test_proth_same_residue() {
+        // 3 doesn't divide 5*2^26605+1, so both take the whole path.
+        let five_26605 = Proth { t: 5, e: 26605 };
+        let r_rug = proth::<Rug>(five_26605);
+        let r_native = proth::<Native>(five_26605);
+        assert!(!r_rug.0);
+        assert!(!r_native.0);
+        assert_eq!(r_rug.1.to_string(), r_native.1.to_string());
+        assert_eq!(r_rug.1.to_string(), simple(five_26605).0.to_string());
+    }
+    #[test]
+    fn test_prp() {
+        let five_1947 = Proth { t: 5, e: 1947 };
+        let five_1946 = Proth { t: 5, e: 1946 };
+        assert!(prp::<Rug>(five_1947).0);
+        assert!(prp::<Native>(five_1947).0);
+        assert!(!prp::<Native>(five_1946).0);
+        assert!(prp::<Native32>(five_1947).0);
+    }
+}
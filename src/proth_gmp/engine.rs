@@ -0,0 +1,45 @@
+use crate::proth::Proth;
+use crate::proth_gmp::reducer::ModReducer;
+use crate::proth_gmp::multiplier::Multiplier;
+use crate::proth_gmp::limbs::LimbBuf;
+use rug::Integer;
+
+// Computes 3^((n-1)/2) mod n by right-to-left binary exponentiation,
+// taking every product with M and reducing it with R.
+pub fn exponentiate<R: ModReducer, M: Multiplier>(n : Proth) -> (Integer, Integer) {
+    let two_to_the_e : Integer = Integer::from(Integer::u_pow_u(2, n.e));
+    let n_full : Integer = two_to_the_e * n.t + 1;
+    let n_minus_one_over_two : Integer = Integer::from(&n_full - 1) / 2;
+    println!("n: {:?} bts", n_full.significant_bits());
+    let mut reducer = R::new(n, &n_full);
+    let n_sz = LimbBuf::from_integer_exact(&n_full).len();
+    let mut multiplier = M::new(n, n_sz);
+    let double_sz = n_sz * 2;
+    println!("n size: {} double: {}", n_sz, double_sz);
+    let mut rr = LimbBuf::from_integer(&reducer.to_form(&Integer::from(1)), n_sz);
+    let mut a = LimbBuf::from_integer(&reducer.to_form(&Integer::from(3)), n_sz);
+    let mut t = LimbBuf::zeroed(double_sz);
+    let mut i : u32 = 0;
+    let bits : u32 = n_minus_one_over_two.significant_bits();
+    println!("n_minus_one_over_two: {} bits", bits);
+    while i < bits {
+        let bit = n_minus_one_over_two.get_bit(i);
+        if bit {
+            multiplier.mul(&mut t, &rr, &a);
+            reducer.reduce(&mut rr, &mut t);
+        }
+        // square
+        multiplier.sqr(&mut t, &a);
+        reducer.reduce(&mut a, &mut t);
+        if i.is_multiple_of(100) || i < 100 {
+            println!("{}/{} {}", i, bits, (i as f32)/(bits as f32));
+        }
+        i += 1;
+    }
+    println!("done");
+    multiplier.report();
+    let r : Integer = reducer.out_of_form(&rr.to_integer());
+    let r_minus_p : Integer = &r - n_full;
+    println!("{:?}", r_minus_p);
+    return (r, r_minus_p);
+}
@@ -0,0 +1,307 @@
+use rug::Integer;
+use gmp_mpfr_sys::gmp;
+use gmp_mpfr_sys::gmp::{limb_t, size_t};
+use std::cmp::Ordering;
+use std::ops::{Deref, DerefMut};
+
+// Owned, fixed-length limb storage for the mpn layer, least significant
+// limb first. Derefs to a slice so the wrappers below can work on
+// sub-ranges (like the top half of a double-width product) without
+// pointer arithmetic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimbBuf {
+    limbs : Vec<limb_t>,
+}
+
+impl LimbBuf {
+    pub fn zeroed(len : usize) -> Self {
+        LimbBuf { limbs: vec![0; len] }
+    }
+
+    // Copies a non-negative Integer into a buffer of exactly len limbs.
+    pub fn from_integer(a : &Integer, len : usize) -> Self {
+        assert!(*a >= 0);
+        let mut b = LimbBuf::zeroed(len);
+        let a_ptr = a.as_raw();
+        unsafe {
+            let a_sz = gmp::mpz_size(a_ptr);
+            assert!(a_sz <= len, "{} limbs don't fit in {}", a_sz, len);
+            if a_sz > 0 {
+                gmp::mpn_copyi(b.limbs.as_mut_ptr(), gmp::mpz_limbs_read(a_ptr), a_sz as size_t);
+            }
+        }
+        return b;
+    }
+
+    // Copies a non-negative Integer into a buffer just big enough for it.
+    pub fn from_integer_exact(a : &Integer) -> Self {
+        let a_sz : usize;
+        unsafe {
+            a_sz = gmp::mpz_size(a.as_raw());
+        }
+        return LimbBuf::from_integer(a, a_sz);
+    }
+
+    pub fn to_integer(&self) -> Integer {
+        return to_integer(&self.limbs);
+    }
+
+    // The number of limbs without the high zero limbs.
+    pub fn significant_len(&self) -> usize {
+        return significant_len(&self.limbs);
+    }
+}
+
+impl Deref for LimbBuf {
+    type Target = [limb_t];
+
+    fn deref(&self) -> &[limb_t] {
+        &self.limbs
+    }
+}
+
+impl DerefMut for LimbBuf {
+    fn deref_mut(&mut self) -> &mut [limb_t] {
+        &mut self.limbs
+    }
+}
+
+impl From<&LimbBuf> for Integer {
+    fn from(b : &LimbBuf) -> Integer {
+        b.to_integer()
+    }
+}
+
+pub fn significant_len(a : &[limb_t]) -> usize {
+    let mut len = a.len();
+    while len > 0 && a[len - 1] == 0 {
+        len -= 1;
+    }
+    return len;
+}
+
+pub fn to_integer(a : &[limb_t]) -> Integer {
+    let mut r = Integer::new();
+    let len = significant_len(a);
+    if len == 0 {
+        return r;
+    }
+    let r_ptr = r.as_raw_mut();
+    unsafe {
+        let r_0 = gmp::mpz_limbs_write(r_ptr, len as size_t);
+        gmp::mpn_copyi(r_0, a.as_ptr(), len as size_t);
+        gmp::mpz_limbs_finish(r_ptr, len as size_t);
+    }
+    return r;
+}
+
+// Safe wrappers around the mpn functions we use.
+//
+// Everything GMP needs to stay in bounds is checked with assert!, those
+// are a couple of compares per call. The sizes GMP's algorithms expect
+// on top of that (exact output widths, normalized divisors and the like)
+// are debug_assert!s so that release builds of the hot loop don't pay
+// for them. Borrowing rules out overlap, so the *_assign forms exist for
+// the cases where GMP allows the result to alias the first operand.
+
+// r = a * b, with a.len() >= b.len(). Returns the most significant limb.
+pub fn mul(r : &mut [limb_t], a : &[limb_t], b : &[limb_t]) -> limb_t {
+    assert!(!b.is_empty());
+    assert!(a.len() >= b.len());
+    assert!(r.len() >= a.len() + b.len());
+    debug_assert_eq!(r.len(), a.len() + b.len());
+    unsafe {
+        return gmp::mpn_mul(
+            r.as_mut_ptr(),
+            a.as_ptr(), a.len() as size_t,
+            b.as_ptr(), b.len() as size_t
+        );
+    }
+}
+
+// r = a * a
+pub fn sqr(r : &mut [limb_t], a : &[limb_t]) {
+    assert!(!a.is_empty());
+    assert!(r.len() >= a.len() * 2);
+    debug_assert_eq!(r.len(), a.len() * 2);
+    unsafe {
+        gmp::mpn_sqr(r.as_mut_ptr(), a.as_ptr(), a.len() as size_t);
+    }
+}
+
+// q = n / d, r = n % d. d must have a non-zero top limb.
+pub fn tdiv_qr(q : &mut [limb_t], r : &mut [limb_t], n : &[limb_t], d : &[limb_t]) {
+    assert!(!d.is_empty());
+    assert!(d[d.len() - 1] != 0);
+    assert!(n.len() >= d.len());
+    assert!(q.len() > n.len() - d.len());
+    assert!(r.len() >= d.len());
+    debug_assert_eq!(r.len(), d.len());
+    unsafe {
+        gmp::mpn_tdiv_qr(
+            q.as_mut_ptr(), r.as_mut_ptr(), 0,
+            n.as_ptr(), n.len() as size_t,
+            d.as_ptr(), d.len() as size_t
+        );
+    }
+}
+
+// r = a - b, with a.len() >= b.len(). Returns the borrow.
+pub fn sub(r : &mut [limb_t], a : &[limb_t], b : &[limb_t]) -> limb_t {
+    assert!(!a.is_empty());
+    assert!(a.len() >= b.len());
+    assert!(r.len() >= a.len());
+    debug_assert_eq!(r.len(), a.len());
+    if b.is_empty() {
+        r[..a.len()].copy_from_slice(a);
+        return 0;
+    }
+    unsafe {
+        return gmp::mpn_sub(
+            r.as_mut_ptr(),
+            a.as_ptr(), a.len() as size_t,
+            b.as_ptr(), b.len() as size_t
+        );
+    }
+}
+
+// a -= b, with a.len() >= b.len(). Returns the borrow.
+pub fn sub_assign(a : &mut [limb_t], b : &[limb_t]) -> limb_t {
+    assert!(!a.is_empty());
+    assert!(a.len() >= b.len());
+    if b.is_empty() {
+        return 0;
+    }
+    unsafe {
+        return gmp::mpn_sub(
+            a.as_mut_ptr(),
+            a.as_ptr(), a.len() as size_t,
+            b.as_ptr(), b.len() as size_t
+        );
+    }
+}
+
+// a += b, with a.len() >= b.len(). Returns the carry.
+pub fn add_assign(a : &mut [limb_t], b : &[limb_t]) -> limb_t {
+    assert!(!a.is_empty());
+    assert!(a.len() >= b.len());
+    if b.is_empty() {
+        return 0;
+    }
+    unsafe {
+        return gmp::mpn_add(
+            a.as_mut_ptr(),
+            a.as_ptr(), a.len() as size_t,
+            b.as_ptr(), b.len() as size_t
+        );
+    }
+}
+
+// a += b for a single limb b. Returns the carry.
+pub fn add_1_assign(a : &mut [limb_t], b : limb_t) -> limb_t {
+    if a.is_empty() {
+        return b;
+    }
+    unsafe {
+        return gmp::mpn_add_1(a.as_mut_ptr(), a.as_ptr(), a.len() as size_t, b);
+    }
+}
+
+// r += a * b for a single limb b, over a.len() limbs of r. Returns the
+// carry out of the top.
+pub fn addmul_1(r : &mut [limb_t], a : &[limb_t], b : limb_t) -> limb_t {
+    assert!(!a.is_empty());
+    assert!(r.len() >= a.len());
+    unsafe {
+        return gmp::mpn_addmul_1(r.as_mut_ptr(), a.as_ptr(), a.len() as size_t, b);
+    }
+}
+
+// q = a / d for a single limb d. Returns the remainder.
+pub fn divrem_1(q : &mut [limb_t], a : &[limb_t], d : limb_t) -> limb_t {
+    assert!(d != 0);
+    assert!(!a.is_empty());
+    assert!(q.len() >= a.len());
+    debug_assert_eq!(q.len(), a.len());
+    unsafe {
+        return gmp::mpn_divrem_1(q.as_mut_ptr(), 0, a.as_ptr(), a.len() as size_t, d);
+    }
+}
+
+// r = a >> count for 0 <= count < limb_t::BITS.
+pub fn rshift(r : &mut [limb_t], a : &[limb_t], count : u32) {
+    assert!(count < limb_t::BITS);
+    assert!(r.len() >= a.len());
+    debug_assert_eq!(r.len(), a.len());
+    if count == 0 {
+        r[..a.len()].copy_from_slice(a);
+        return;
+    }
+    if a.is_empty() {
+        return;
+    }
+    unsafe {
+        gmp::mpn_rshift(r.as_mut_ptr(), a.as_ptr(), a.len() as size_t, count);
+    }
+}
+
+// Compares two numbers of the same length.
+pub fn cmp(a : &[limb_t], b : &[limb_t]) -> Ordering {
+    assert_eq!(a.len(), b.len());
+    if a.is_empty() {
+        return Ordering::Equal;
+    }
+    let c : i32;
+    unsafe {
+        c = gmp::mpn_cmp(a.as_ptr(), b.as_ptr(), a.len() as size_t) as i32;
+    }
+    return c.cmp(&0);
+}
+
+pub fn is_zero(a : &[limb_t]) -> bool {
+    return a.iter().all(|&x| x == 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let a = Integer::from(Integer::u_pow_u(3, 200)) + 1;
+        let b = LimbBuf::from_integer(&a, 8);
+        assert_eq!(b.len(), 8);
+        assert_eq!(b.to_integer(), a);
+        assert_eq!(LimbBuf::from_integer_exact(&a).len(), b.significant_len());
+        assert_eq!(LimbBuf::zeroed(3).to_integer(), 0);
+    }
+
+    #[test]
+    fn test_mul_sqr_div() {
+        let a = Integer::from(Integer::u_pow_u(7, 100));
+        let d = Integer::from(Integer::u_pow_u(5, 90)) + 3;
+        let ab = LimbBuf::from_integer_exact(&a);
+        let db = LimbBuf::from_integer_exact(&d);
+        let mut p = LimbBuf::zeroed(ab.len() * 2);
+        sqr(&mut p, &ab);
+        assert_eq!(p.to_integer(), Integer::from(&a * &a));
+        let mut p2 = LimbBuf::zeroed(ab.len() + db.len());
+        mul(&mut p2, &ab, &db);
+        assert_eq!(p2.to_integer(), Integer::from(&a * &d));
+        let mut q = LimbBuf::zeroed(p.len() - db.len() + 1);
+        let mut r = LimbBuf::zeroed(db.len());
+        tdiv_qr(&mut q, &mut r, &p, &db);
+        let sq = Integer::from(&a * &a);
+        assert_eq!(q.to_integer(), Integer::from(&sq / &d));
+        assert_eq!(r.to_integer(), Integer::from(&sq % &d));
+        assert_eq!(cmp(&r, &db), Ordering::Less);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mul_too_small() {
+        let a = LimbBuf::zeroed(4);
+        let mut r = LimbBuf::zeroed(7);
+        mul(&mut r, &a, &a);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::proth::Proth;
+use crate::proth_gmp::limbs;
+use gmp_mpfr_sys::gmp::limb_t;
+use mulch3::{Fft, Ssa};
+
+// A way of taking the double-width products the exponentiation engine
+// reduces. t has room for the whole product.
+pub trait Multiplier {
+    fn new(n : Proth, n_sz: usize) -> Self where Self: Sized;
+
+    fn mul(&mut self, t : &mut [limb_t], a : &[limb_t], b : &[limb_t]);
+
+    fn sqr(&mut self, t : &mut [limb_t], a : &[limb_t]);
+
+    // Called once the exponentiation is done.
+    fn report(&self) {}
+}
+
+// mpn_mul and mpn_sqr.
+pub struct Gmp;
+
+impl Multiplier for Gmp {
+    fn new(_n : Proth, _n_sz : usize) -> Self {
+        Gmp
+    }
+
+    fn mul(&mut self, t : &mut [limb_t], a : &[limb_t], b : &[limb_t]) {
+        limbs::mul(t, a, b);
+    }
+
+    fn sqr(&mut self, t : &mut [limb_t], a : &[limb_t]) {
+        limbs::sqr(t, a);
+    }
+}
+
+// mulch3's floating-point FFT, which checks the round-off of every
+// product and redoes it with a longer transform if it was too close.
+pub struct FftMultiplier {
+    fft : Fft,
+}
+
+impl Multiplier for FftMultiplier {
+    fn new(_n : Proth, _n_sz : usize) -> Self {
+        FftMultiplier { fft: Fft::new() }
+    }
+
+    fn mul(&mut self, t : &mut [limb_t], a : &[limb_t], b : &[limb_t]) {
+        self.fft.mul(t, a, b);
+    }
+
+    fn sqr(&mut self, t : &mut [limb_t], a : &[limb_t]) {
+        self.fft.sqr(t, a);
+    }
+
+    fn report(&self) {
+        println!("fft: {} bits per digit, length {}, max round-off error {}",
+            self.fft.digit_bits(), self.fft.transform_len(), self.fft.max_error());
+    }
+}
+
+// mulch3's Schönhage–Strassen, exact like GMP. For t = 1, the Fermat
+// numbers of Pépin's test among them, n is 2^e+1 and the products are
+// taken straight modulo n, leaving the reducer nothing to do.
+pub struct SsaMultiplier {
+    ssa : Ssa,
+    n_sz : usize,
+    fermat : Option<usize>,
+}
+
+impl Multiplier for SsaMultiplier {
+    fn new(n : Proth, n_sz : usize) -> Self {
+        let fermat = if n.t == 1 { Some(n.e as usize) } else { None };
+        SsaMultiplier { ssa: Ssa::new(), n_sz, fermat }
+    }
+
+    fn mul(&mut self, t : &mut [limb_t], a : &[limb_t], b : &[limb_t]) {
+        match self.fermat {
+            Some(e) => {
+                let (lo, hi) = t.split_at_mut(self.n_sz);
+                self.ssa.mul_mod(lo, a, b, e);
+                for x in hi.iter_mut() {
+                    *x = 0;
+                }
+            }
+            None => self.ssa.mul(t, a, b),
+        }
+    }
+
+    fn sqr(&mut self, t : &mut [limb_t], a : &[limb_t]) {
+        match self.fermat {
+            Some(e) => {
+                let (lo, hi) = t.split_at_mut(self.n_sz);
+                self.ssa.sqr_mod(lo, a, e);
+                for x in hi.iter_mut() {
+                    *x = 0;
+                }
+            }
+            None => self.ssa.sqr(t, a),
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use crate::proth::Proth;
+use crate::proth_gmp::limbs;
+use crate::proth_gmp::limbs::LimbBuf;
+use rug::Integer;
+use gmp_mpfr_sys::gmp::limb_t;
+use std::cmp::Ordering;
+
+const LIMB_BITS : u32 = limb_t::BITS;
+
+// A way of reducing a double-width product modulo n.
+//
+// The exponentiation engine multiplies two n_sz-limb residues into a
+// 2*n_sz-limb scratch buffer and hands it to the reducer, which leaves
+// the result in the low n_sz limbs of `r`. Reducers that keep residues
+// in some other representation (Montgomery) convert in and out with
+// to_form/out_of_form; everyone else can use the defaults.
+pub trait ModReducer {
+    fn new(n : Proth, n_full : &Integer) -> Self where Self: Sized;
+
+    fn to_form(&self, a : &Integer) -> Integer {
+        Integer::from(a)
+    }
+
+    fn out_of_form(&self, a : &Integer) -> Integer {
+        Integer::from(a)
+    }
+
+    // t has 2*n_sz limbs and may be clobbered, r has n_sz limbs.
+    fn reduce(&mut self, r : &mut [limb_t], t : &mut [limb_t]);
+}
+
+// Plain long division with mpn_tdiv_qr, what gmp_low used to do.
+pub struct Division {
+    n : LimbBuf,
+    q : LimbBuf,
+}
+
+impl ModReducer for Division {
+    fn new(_n : Proth, n_full : &Integer) -> Self {
+        let n = LimbBuf::from_integer_exact(n_full);
+        let q = LimbBuf::zeroed(n.len() + 1);
+        Division { n, q }
+    }
+
+    fn reduce(&mut self, r : &mut [limb_t], t : &mut [limb_t]) {
+        limbs::tdiv_qr(&mut self.q, r, t, &self.n);
+    }
+}
+
+// mpz_mod on the whole product, what gmp_medium used to do. The product
+// goes through an Integer so the division is GMP's mpz-level one rather
+// than a direct mpn_tdiv_qr.
+pub struct Mpz {
+    n_full : Integer,
+}
+
+impl ModReducer for Mpz {
+    fn new(_n : Proth, n_full : &Integer) -> Self {
+        Mpz { n_full: Integer::from(n_full) }
+    }
+
+    fn reduce(&mut self, r : &mut [limb_t], t : &mut [limb_t]) {
+        let mut p = limbs::to_integer(t);
+        p %= &self.n_full;
+        r.copy_from_slice(&LimbBuf::from_integer(&p, r.len()));
+    }
+}
+
+fn find_m(n : & Integer) -> (Integer, usize) {
+    // find 1/n mod 2^(64*w) ?
+    // euclidean algorithm?
+    assert!(n.is_odd());
+    // since n is odd
+    let n_copy = Integer::from(n);
+    let mut two_to_k = Integer::from(1);
+    let n_sz = LimbBuf::from_integer_exact(n).len();
+    let target_sz : i32 = (n_sz as i32) * 2;
+    let bits : i32 = target_sz * (LIMB_BITS as i32);
+    two_to_k <<= bits; // compute 2^bits
+    let (mut m, _r) = two_to_k.div_rem_floor(n_copy);
+    m.shrink_to_fit();
+    return (m, bits as usize);
+}
+
+// Barrett reduction: multiply by a precomputed m = 2^k/n instead of
+// dividing by n.
+pub struct Barrett {
+    n : LimbBuf,
+    m : LimbBuf,
+    m_shift_limbs : usize,
+    q2 : LimbBuf,
+    qn : LimbBuf,
+}
+
+impl ModReducer for Barrett {
+    fn new(_n : Proth, n_full : &Integer) -> Self {
+        let n = LimbBuf::from_integer_exact(n_full);
+        let n_sz = n.len();
+        let (m, m_shift) = find_m(n_full);
+        assert_eq!(m_shift % (LIMB_BITS as usize), 0);
+        let m_shift_limbs = m_shift / (LIMB_BITS as usize);
+        let m = LimbBuf::from_integer_exact(&m);
+        let q2_sz = m.len() + n_sz * 2;
+        let q2_shifted_sz = q2_sz - m_shift_limbs;
+        println!("q2_sz: {} m_shift_limbs: {} q2_shifted_sz: {}", q2_sz, m_shift_limbs, q2_shifted_sz);
+        Barrett {
+            n,
+            m,
+            m_shift_limbs,
+            q2: LimbBuf::zeroed(q2_sz),
+            qn: LimbBuf::zeroed(q2_shifted_sz + n_sz),
+        }
+    }
+
+    fn reduce(&mut self, r : &mut [limb_t], t : &mut [limb_t]) {
+        let n_sz = self.n.len();
+        // q := (t * m) >> k in barrett reduction
+        limbs::mul(&mut self.q2, t, &self.m);
+        // compute q * n
+        limbs::mul(&mut self.qn, &self.q2[self.m_shift_limbs..], &self.n);
+        debug_assert!(limbs::is_zero(&self.qn[t.len()..]));
+        limbs::sub_assign(t, &self.qn[..t.len()]);
+        // the estimate of q can be a little low
+        while !limbs::is_zero(&t[n_sz..])
+            || limbs::cmp(&t[..n_sz], &self.n) != Ordering::Less
+        {
+            limbs::sub_assign(t, &self.n);
+        }
+        r.copy_from_slice(&t[..n_sz]);
+    }
+}
+
+// Montgomery reduction, REDC one limb at a time with R = 2^(64*n_sz).
+pub struct Montgomery {
+    n : LimbBuf,
+    n_full : Integer,
+    n_inv : limb_t,
+    r_inv : Integer,
+    shift : u32,
+}
+
+impl ModReducer for Montgomery {
+    fn new(_n : Proth, n_full : &Integer) -> Self {
+        assert!(n_full.is_odd());
+        let n = LimbBuf::from_integer_exact(n_full);
+        // newton iteration for 1/n mod 2^64, each step doubles the bits
+        let mut inv : limb_t = 1;
+        for _ in 0..7 {
+            inv = inv.wrapping_mul((2 as limb_t).wrapping_sub(n[0].wrapping_mul(inv)));
+        }
+        assert_eq!(n[0].wrapping_mul(inv), 1);
+        let shift = (n.len() as u32) * LIMB_BITS;
+        let r = Integer::from(1) << shift;
+        let r_inv = r.invert(n_full).expect("n must be odd");
+        Montgomery {
+            n,
+            n_full: Integer::from(n_full),
+            n_inv: inv.wrapping_neg(),
+            r_inv,
+            shift,
+        }
+    }
+
+    fn to_form(&self, a : &Integer) -> Integer {
+        Integer::from(a << self.shift) % &self.n_full
+    }
+
+    fn out_of_form(&self, a : &Integer) -> Integer {
+        Integer::from(a * &self.r_inv) % &self.n_full
+    }
+
+    fn reduce(&mut self, r : &mut [limb_t], t : &mut [limb_t]) {
+        let n_sz = self.n.len();
+        let mut carry : limb_t = 0;
+        for i in 0..n_sz {
+            let m = t[i].wrapping_mul(self.n_inv);
+            let c = limbs::addmul_1(&mut t[i..], &self.n, m);
+            carry += limbs::add_1_assign(&mut t[i + n_sz..], c);
+        }
+        let t_hi = &t[n_sz..];
+        if carry != 0 || limbs::cmp(t_hi, &self.n) != Ordering::Less {
+            limbs::sub(r, t_hi, &self.n);
+        } else {
+            r.copy_from_slice(t_hi);
+        }
+    }
+}
+
+// Special-form reduction for n = t*2^e+1.
+//
+// With x = hi*2^e + lo, q = floor(hi/t) is floor(x/(n-1)), which is at
+// most one more than floor(x/n) when x < n^2. Then
+// x - q*n = ((hi mod t)*2^e + lo) - q, which only needs one fixup.
+pub struct ProthForm {
+    n : LimbBuf,
+    t : limb_t,
+    e : usize,
+    hi : LimbBuf,
+    q : LimbBuf,
+}
+
+impl ModReducer for ProthForm {
+    fn new(n : Proth, n_full : &Integer) -> Self {
+        let n_buf = LimbBuf::from_integer_exact(n_full);
+        let e = n.e as usize;
+        let hi_sz = n_buf.len() * 2 - e / (LIMB_BITS as usize);
+        ProthForm {
+            n: n_buf,
+            t: n.t as limb_t,
+            e,
+            hi: LimbBuf::zeroed(hi_sz),
+            q: LimbBuf::zeroed(hi_sz),
+        }
+    }
+
+    fn reduce(&mut self, r : &mut [limb_t], t : &mut [limb_t]) {
+        let n_sz = self.n.len();
+        let e_limbs = self.e / (LIMB_BITS as usize);
+        let e_bits = (self.e % (LIMB_BITS as usize)) as u32;
+        // hi = x >> e
+        limbs::rshift(&mut self.hi, &t[e_limbs..], e_bits);
+        // q = hi / t
+        let rem = limbs::divrem_1(&mut self.q, &self.hi, self.t);
+        assert!(limbs::is_zero(&self.q[n_sz..]));
+        // r = (hi mod t)*2^e + lo
+        for x in r.iter_mut() {
+            *x = 0;
+        }
+        r[..e_limbs].copy_from_slice(&t[..e_limbs]);
+        let mut top : u128 = 0;
+        if e_bits != 0 {
+            top = (t[e_limbs] as u128) & ((1 << e_bits) - 1);
+        }
+        top |= (rem as u128) << e_bits;
+        r[e_limbs] = top as limb_t;
+        if e_limbs + 1 < n_sz {
+            r[e_limbs + 1] = (top >> LIMB_BITS) as limb_t;
+        }
+        // r -= q, adding n back if that went negative
+        let borrow = limbs::sub_assign(r, &self.q[..n_sz]);
+        if borrow != 0 {
+            limbs::add_assign(r, &self.n);
+        }
+    }
+}